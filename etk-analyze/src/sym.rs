@@ -877,6 +877,67 @@ mod tests {
         assert_eq!(ast, expected);
     }
 
+    #[test]
+    fn int_min_slt_zero() {
+        let config = z3::Config::new();
+        let ctx = z3::Context::new(&config);
+
+        let int_min = hex!("8000000000000000000000000000000000000000000000000000000000000000");
+        let expr = Expr::constant(int_min).s_lt(&Expr::constant(&[0]));
+        let ast = expr.to_z3(&ctx).simplify();
+        let expected = BV::from_u64(&ctx, 1, 256);
+        assert_eq!(ast, expected);
+    }
+
+    #[test]
+    fn int_min_sgt_int_max() {
+        let config = z3::Config::new();
+        let ctx = z3::Context::new(&config);
+
+        let int_min = hex!("8000000000000000000000000000000000000000000000000000000000000000");
+        let int_max = hex!("7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff");
+        let expr = Expr::constant(int_min).s_gt(&Expr::constant(int_max));
+        let ast = expr.to_z3(&ctx).simplify();
+        let expected = BV::from_u64(&ctx, 0, 256);
+        assert_eq!(ast, expected);
+    }
+
+    #[test]
+    fn int_max_sgt_int_min() {
+        let config = z3::Config::new();
+        let ctx = z3::Context::new(&config);
+
+        let int_min = hex!("8000000000000000000000000000000000000000000000000000000000000000");
+        let int_max = hex!("7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff");
+        let expr = Expr::constant(int_max).s_gt(&Expr::constant(int_min));
+        let ast = expr.to_z3(&ctx).simplify();
+        let expected = BV::from_u64(&ctx, 1, 256);
+        assert_eq!(ast, expected);
+    }
+
+    #[test]
+    fn neg_one_sgt_zero() {
+        let config = z3::Config::new();
+        let ctx = z3::Context::new(&config);
+
+        let expr = Expr::constant(&[0xff; 32]).s_gt(&Expr::constant(&[0]));
+        let ast = expr.to_z3(&ctx).simplify();
+        let expected = BV::from_u64(&ctx, 0, 256);
+        assert_eq!(ast, expected);
+    }
+
+    #[test]
+    fn neg_one_gt_zero() {
+        let config = z3::Config::new();
+        let ctx = z3::Context::new(&config);
+
+        // Unsigned comparison treats the same bits as the maximum value.
+        let expr = Expr::constant(&[0xff; 32]).gt(&Expr::constant(&[0]));
+        let ast = expr.to_z3(&ctx).simplify();
+        let expected = BV::from_u64(&ctx, 1, 256);
+        assert_eq!(ast, expected);
+    }
+
     #[test]
     fn three_eq_three() {
         let config = z3::Config::new();
@@ -0,0 +1,390 @@
+//! A container bundling assembled code with the metadata needed to recover
+//! labels and sources.
+//!
+//! See [`Artifact`] for more details, including the binary format.
+mod error {
+    use snafu::{Backtrace, Snafu};
+
+    /// Errors that may arise while reading an [`super::Artifact`].
+    #[derive(Debug, Snafu)]
+    #[non_exhaustive]
+    #[snafu(context(suffix(false)), visibility(pub(super)))]
+    pub enum Error {
+        /// An i/o error.
+        #[snafu(display("an i/o error occurred while reading an artifact"))]
+        #[non_exhaustive]
+        Io {
+            /// The underlying source of this error.
+            source: std::io::Error,
+
+            /// The location of the error.
+            backtrace: Backtrace,
+        },
+
+        /// The input did not begin with the artifact magic bytes.
+        #[snafu(display("input is not an etk artifact"))]
+        #[non_exhaustive]
+        BadMagic {
+            /// The location of the error.
+            backtrace: Backtrace,
+        },
+
+        /// The artifact was written with an unknown version of the format.
+        #[snafu(display("unsupported artifact version {}", version))]
+        #[non_exhaustive]
+        UnsupportedVersion {
+            /// The version found in the input.
+            version: u8,
+
+            /// The location of the error.
+            backtrace: Backtrace,
+        },
+
+        /// A label or path was not valid UTF-8.
+        #[snafu(display("artifact contains a string that is not valid UTF-8"))]
+        #[non_exhaustive]
+        InvalidUtf8 {
+            /// The underlying source of this error.
+            source: std::string::FromUtf8Error,
+
+            /// The location of the error.
+            backtrace: Backtrace,
+        },
+    }
+}
+
+pub use self::error::Error;
+
+use snafu::{ensure, ResultExt};
+
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+const MAGIC: [u8; 4] = *b"\0etk";
+const VERSION: u8 = 1;
+
+/// A range of assembled code, and the source file it was assembled from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceRange {
+    /// Position of the first byte of the range in the code.
+    pub offset: usize,
+
+    /// Number of bytes in the range.
+    pub len: usize,
+
+    /// Path of the source file.
+    pub path: PathBuf,
+}
+
+/// Assembled code, along with its label table and source map.
+///
+/// ## Format
+///
+/// Artifacts are serialized as the magic bytes `\0etk`, a one byte version
+/// (currently `1`), and then three sections. Every integer is a big-endian
+/// `u32`, and every string is its length followed by its UTF-8 bytes.
+///
+///  1. The code, as its length followed by the raw bytes.
+///  2. The labels, as a count followed by each name and its position.
+///  3. The source map, as a count followed by each offset, length, and path.
+///
+/// ## Example
+///
+/// ```rust
+/// use etk_asm::artifact::Artifact;
+/// use etk_asm::ingest::Ingest;
+/// #
+/// # use etk_asm::ingest::Error;
+///
+/// let text = r#"
+///     push1 lbl
+///     lbl:
+///     jumpdest
+/// "#;
+///
+/// let mut output = Vec::new();
+/// let mut ingest = Ingest::with_artifact(&mut output);
+/// ingest.ingest("./example.etk", &text)?;
+///
+/// let mut bytes = Vec::new();
+/// ingest.artifact().unwrap().write_to(&mut bytes).unwrap();
+///
+/// let artifact = Artifact::read_from(bytes.as_slice()).unwrap();
+/// assert_eq!(artifact.labels["lbl"], 2);
+/// # Result::<(), Error>::Ok(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Artifact {
+    /// The assembled code.
+    pub code: Vec<u8>,
+
+    /// Every label and its position in `code`.
+    ///
+    /// Labels declared in an `%include`d file are private to it, so they are
+    /// prefixed with the file's path relative to the root (the directory of
+    /// the first file assembled), as in `path/to/file.etk:label`. If the
+    /// same file is included more than once, the path of the second copy is
+    /// followed by `#2`, and so on.
+    pub labels: BTreeMap<String, usize>,
+
+    /// Which source file each range of `code` was assembled from.
+    pub sources: Vec<SourceRange>,
+}
+
+impl Artifact {
+    /// Serialize `self` into `writer`.
+    pub fn write_to<W>(&self, mut writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&[VERSION])?;
+
+        write_bytes(&mut writer, &self.code)?;
+
+        write_len(&mut writer, self.labels.len())?;
+        for (name, position) in &self.labels {
+            write_bytes(&mut writer, name.as_bytes())?;
+            write_len(&mut writer, *position)?;
+        }
+
+        write_len(&mut writer, self.sources.len())?;
+        for source in &self.sources {
+            write_len(&mut writer, source.offset)?;
+            write_len(&mut writer, source.len)?;
+            write_bytes(&mut writer, source.path.to_string_lossy().as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Deserialize an `Artifact` from `reader`.
+    pub fn read_from<R>(mut reader: R) -> Result<Self, Error>
+    where
+        R: Read,
+    {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic).context(error::Io)?;
+        ensure!(magic == MAGIC, error::BadMagic);
+
+        let mut version = [0u8; 1];
+        reader.read_exact(&mut version).context(error::Io)?;
+        ensure!(
+            version[0] == VERSION,
+            error::UnsupportedVersion {
+                version: version[0]
+            }
+        );
+
+        let code = read_bytes(&mut reader)?;
+
+        let mut labels = BTreeMap::new();
+        for _ in 0..read_len(&mut reader)? {
+            let name = read_string(&mut reader)?;
+            let position = read_len(&mut reader)?;
+            labels.insert(name, position);
+        }
+
+        let mut sources = Vec::new();
+        for _ in 0..read_len(&mut reader)? {
+            let offset = read_len(&mut reader)?;
+            let len = read_len(&mut reader)?;
+            let path = read_string(&mut reader)?.into();
+            sources.push(SourceRange { offset, len, path });
+        }
+
+        Ok(Self {
+            code,
+            labels,
+            sources,
+        })
+    }
+}
+
+fn write_len<W>(writer: &mut W, len: usize) -> io::Result<()>
+where
+    W: Write,
+{
+    let len: u32 = len
+        .try_into()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    writer.write_all(&len.to_be_bytes())
+}
+
+fn write_bytes<W>(writer: &mut W, bytes: &[u8]) -> io::Result<()>
+where
+    W: Write,
+{
+    write_len(writer, bytes.len())?;
+    writer.write_all(bytes)
+}
+
+fn read_len<R>(reader: &mut R) -> Result<usize, Error>
+where
+    R: Read,
+{
+    let mut be_bytes = [0u8; 4];
+    reader.read_exact(&mut be_bytes).context(error::Io)?;
+    Ok(u32::from_be_bytes(be_bytes) as usize)
+}
+
+fn read_bytes<R>(reader: &mut R) -> Result<Vec<u8>, Error>
+where
+    R: Read,
+{
+    let len = read_len(reader)?;
+    let mut bytes = Vec::new();
+    reader
+        .take(len as u64)
+        .read_to_end(&mut bytes)
+        .context(error::Io)?;
+
+    if bytes.len() != len {
+        let err = io::Error::from(io::ErrorKind::UnexpectedEof);
+        return Err(err).context(error::Io);
+    }
+
+    Ok(bytes)
+}
+
+fn read_string<R>(reader: &mut R) -> Result<String, Error>
+where
+    R: Read,
+{
+    String::from_utf8(read_bytes(reader)?).context(error::InvalidUtf8)
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use crate::ingest::Ingest;
+
+    use hex_literal::hex;
+
+    use super::*;
+
+    #[test]
+    fn round_trip_ingest() {
+        let text = r#"
+            push1 end
+            jump
+            start:
+            jumpdest
+            end:
+            jumpdest
+        "#;
+
+        let mut output = Vec::new();
+        let mut ingest = Ingest::with_artifact(&mut output);
+        ingest.ingest("./main.etk", text).unwrap();
+
+        let mut bytes = Vec::new();
+        let expected = ingest.artifact().unwrap().clone();
+        expected.write_to(&mut bytes).unwrap();
+        drop(ingest);
+
+        let actual = Artifact::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(actual, expected);
+
+        assert_eq!(actual.code, hex!("6004565b5b"));
+        assert_eq!(actual.code, output);
+        assert_eq!(actual.labels["start"], 3);
+        assert_eq!(actual.labels["end"], 4);
+        assert_eq!(
+            actual.sources,
+            [SourceRange {
+                offset: 0,
+                len: 5,
+                path: "./main.etk".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn import_and_include() {
+        let dir = tempfile::tempdir().unwrap();
+        let imp = dir.path().join("imp.etk");
+        let inc = dir.path().join("inc.etk");
+        let root = dir.path().join("main.etk");
+
+        std::fs::write(&imp, "imported:\njumpdest\npush1 a\n").unwrap();
+        std::fs::write(&inc, "included:\njumpdest\npc\n").unwrap();
+
+        let text = r#"
+            a:
+            jumpdest
+            %import("imp.etk")
+            %include("inc.etk")
+            pc
+            %include("inc.etk")
+            stop
+        "#;
+
+        let mut output = Vec::new();
+        let mut ingest = Ingest::with_artifact(&mut output);
+        ingest.ingest(&root, text).unwrap();
+        let artifact = ingest.artifact().unwrap().clone();
+        drop(ingest);
+
+        assert_eq!(artifact.code, hex!("5b 5b6000 5b58 58 5b58 00"));
+        assert_eq!(artifact.code, output);
+
+        let range = |offset, len, path: &PathBuf| SourceRange {
+            offset,
+            len,
+            path: path.clone(),
+        };
+        assert_eq!(
+            artifact.sources,
+            [
+                range(0, 1, &root),
+                range(1, 3, &imp),
+                range(4, 2, &inc),
+                range(6, 1, &root),
+                range(7, 2, &inc),
+                range(9, 1, &root),
+            ]
+        );
+
+        let expected: BTreeMap<_, _> = vec![
+            ("a".to_owned(), 0),
+            ("imported".to_owned(), 1),
+            ("inc.etk:included".to_owned(), 4),
+            ("inc.etk#2:included".to_owned(), 7),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(artifact.labels, expected);
+    }
+
+    #[test]
+    fn bad_magic() {
+        let err = Artifact::read_from(&b"\0abc\x01"[..]).unwrap_err();
+        assert_matches!(err, Error::BadMagic { .. });
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let err = Artifact::read_from(&b"\0etk\x02"[..]).unwrap_err();
+        assert_matches!(err, Error::UnsupportedVersion { version: 2, .. });
+    }
+
+    #[test]
+    fn truncated() {
+        let mut bytes = Vec::new();
+        Artifact {
+            code: vec![0x00; 8],
+            ..Default::default()
+        }
+        .write_to(&mut bytes)
+        .unwrap();
+
+        bytes.truncate(bytes.len() - 6);
+
+        let err = Artifact::read_from(bytes.as_slice()).unwrap_err();
+        assert_matches!(err, Error::Io { .. });
+    }
+}
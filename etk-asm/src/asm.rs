@@ -9,6 +9,7 @@ mod error {
     use etk_ops::cancun::Op;
     use num_bigint::BigInt;
     use snafu::{Backtrace, Snafu};
+    use std::ops::Range;
 
     /// Errors that can occur while assembling instructions.
     #[derive(Snafu, Debug)]
@@ -22,6 +23,9 @@ mod error {
             /// The name of the conflicting label.
             label: String,
 
+            /// Byte range of the offending instruction in the source, if known.
+            span: Option<Range<usize>>,
+
             /// The location of the error.
             backtrace: Backtrace,
         },
@@ -33,6 +37,9 @@ mod error {
             /// The name of the conflicting macro.
             name: String,
 
+            /// Byte range of the offending instruction in the source, if known.
+            span: Option<Range<usize>>,
+
             /// The location of the error.
             backtrace: Backtrace,
         },
@@ -55,6 +62,9 @@ mod error {
             /// The specifier.
             spec: Op<()>,
 
+            /// Byte range of the offending instruction in the source, if known.
+            span: Option<Range<usize>>,
+
             /// The location of the error.
             backtrace: Backtrace,
         },
@@ -72,6 +82,9 @@ mod error {
             /// The evaluated value of the expression.
             value: BigInt,
 
+            /// Byte range of the offending instruction in the source, if known.
+            span: Option<Range<usize>>,
+
             /// The location of the error.
             backtrace: Backtrace,
         },
@@ -80,6 +93,9 @@ mod error {
         #[snafu(display("value was too large for any push"))]
         #[non_exhaustive]
         UnsizedPushTooLarge {
+            /// Byte range of the offending instruction in the source, if known.
+            span: Option<Range<usize>>,
+
             /// The location of the error.
             backtrace: Backtrace,
         },
@@ -91,6 +107,9 @@ mod error {
             /// The labels that were used without being defined.
             labels: Vec<String>,
 
+            /// Byte range of the offending instruction in the source, if known.
+            span: Option<Range<usize>>,
+
             /// The location of the error.
             backtrace: Backtrace,
         },
@@ -102,6 +121,9 @@ mod error {
             /// The macro that was used without being defined.
             name: String,
 
+            /// Byte range of the offending instruction in the source, if known.
+            span: Option<Range<usize>>,
+
             /// The location of the error.
             backtrace: Backtrace,
         },
@@ -113,10 +135,23 @@ mod error {
             /// The macro that was used without being defined.
             name: String,
 
+            /// Byte range of the offending instruction in the source, if known.
+            span: Option<Range<usize>>,
+
             /// The location of the error.
             backtrace: Backtrace,
         },
 
+        /// Source code passed to [`super::IncrementalAssembler::feed`] failed
+        /// to parse.
+        #[snafu(display("parsing failed: {}", source))]
+        #[non_exhaustive]
+        Parse {
+            /// The next source of this error.
+            #[snafu(backtrace)]
+            source: ParseError,
+        },
+
         /// An import or include failed to parse.
         #[snafu(display("include or import failed to parse: {}", source))]
         #[snafu(context(false))]
@@ -134,6 +169,24 @@ mod error {
             /// The variable that was used without being defined.
             var: String,
 
+            /// Byte range of the offending instruction in the source, if known.
+            span: Option<Range<usize>>,
+
+            /// The location of the error.
+            backtrace: Backtrace,
+        },
+
+        /// A directive that reads files was fed to an
+        /// [`super::IncrementalAssembler`].
+        #[snafu(display("`{}` is not supported in incremental assembly", directive))]
+        #[non_exhaustive]
+        UnsupportedDirective {
+            /// The name of the directive.
+            directive: String,
+
+            /// Byte range of the offending instruction in the source, if known.
+            span: Option<Range<usize>>,
+
             /// The location of the error.
             backtrace: Backtrace,
         },
@@ -141,12 +194,59 @@ mod error {
 }
 
 pub use self::error::Error;
+
+use crate::ast::Node;
 use crate::ops::expression::Error::{UndefinedVariable, UnknownLabel, UnknownMacro};
 use crate::ops::{self, AbstractOp, Assemble, Expression, MacroDefinition};
+use crate::parse::parse_asm;
+use crate::AssembleError;
 use indexmap::IndexMap;
 use num_bigint::BigInt;
 use rand::Rng;
-use std::collections::{hash_map, HashMap, HashSet};
+use snafu::ResultExt;
+use std::collections::{hash_map, HashMap};
+use std::ops::Range;
+
+impl Error {
+    /// Byte range into the source code where the error occurred, if known.
+    ///
+    /// Spans are only known for instructions assembled from source code, for
+    /// example through [`crate::ingest::Ingest`]. An error caused by an
+    /// instruction expanded from a macro points at the macro's invocation.
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            Self::DuplicateLabel { span, .. }
+            | Self::DuplicateMacro { span, .. }
+            | Self::ExpressionTooLarge { span, .. }
+            | Self::ExpressionNegative { span, .. }
+            | Self::UnsizedPushTooLarge { span, .. }
+            | Self::UndeclaredLabels { span, .. }
+            | Self::UndeclaredInstructionMacro { span, .. }
+            | Self::UndeclaredExpressionMacro { span, .. }
+            | Self::UndeclaredVariableMacro { span, .. }
+            | Self::UnsupportedDirective { span, .. } => span.clone(),
+            Self::Parse { source } | Self::ParseInclude { source } => Some(source.span()),
+        }
+    }
+
+    /// A short code identifying the kind of error, which doesn't change
+    /// between releases.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::DuplicateLabel { .. } => "E200",
+            Self::DuplicateMacro { .. } => "E201",
+            Self::ExpressionTooLarge { .. } => "E202",
+            Self::ExpressionNegative { .. } => "E203",
+            Self::UnsizedPushTooLarge { .. } => "E204",
+            Self::UndeclaredLabels { .. } => "E205",
+            Self::UndeclaredInstructionMacro { .. } => "E206",
+            Self::UndeclaredExpressionMacro { .. } => "E207",
+            Self::Parse { source } | Self::ParseInclude { source } => source.code(),
+            Self::UndeclaredVariableMacro { .. } => "E208",
+            Self::UnsupportedDirective { .. } => "E209",
+        }
+    }
+}
 
 /// An item to be assembled, which can be either an [`AbstractOp`],
 /// the inclusion of a new scope or a raw byte sequence.
@@ -199,7 +299,7 @@ impl From<&AbstractOp> for RawOp {
 /// # assert_eq!(result, hex!("58"));
 /// # Result::<(), Error>::Ok(())
 /// ```
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Assembler {
     /// Assembled ops.
     ready: Vec<RawOp>,
@@ -214,11 +314,27 @@ pub struct Assembler {
     declared_macros: HashMap<String, MacroDefinition>,
 
     /// Labels that have been referred to (ex. with push) but
-    /// have not been declared with an `AbstractOp::Label`.
-    undeclared_labels: HashSet<String>,
+    /// have not been declared with an `AbstractOp::Label`, with the span of
+    /// their first use.
+    undeclared_labels: IndexMap<String, Option<Range<usize>>>,
 
     /// Pushes that are variable-sized and need to be backpatched.
     variable_sized_push: Vec<PushDef>,
+
+    /// For each entry in `ready`, the index of the op passed to
+    /// [`Assembler::assemble`] that produced it.
+    origins: Vec<usize>,
+
+    /// Position of each op passed to the most recent call to
+    /// [`Assembler::assemble`].
+    positions: Vec<usize>,
+
+    /// Span in the source of each op passed to the current call to
+    /// [`Assembler::assemble`], if known.
+    spans: Vec<Range<usize>>,
+
+    /// Span of the op currently being pushed.
+    span: Option<Range<usize>>,
 }
 
 /// A label definition.
@@ -280,17 +396,74 @@ impl Assembler {
     where
         O: Into<RawOp> + Clone,
     {
+        self.assemble_spanned(ops, Vec::new())
+    }
+
+    /// Like [`Assembler::assemble`], but `spans` gives the location of each
+    /// op in the source code, which is reported in any errors.
+    pub(crate) fn assemble_spanned<O>(
+        &mut self,
+        ops: &[O],
+        spans: Vec<Range<usize>>,
+    ) -> Result<Vec<u8>, Error>
+    where
+        O: Into<RawOp> + Clone,
+    {
+        self.spans = spans;
         self.declare_macros(ops)?;
 
-        for op in ops {
+        for (index, op) in ops.iter().enumerate() {
+            self.span = self.spans.get(index).cloned();
             self.push(op.clone().into())?;
+            self.origins.resize(self.ready.len(), index);
+        }
+
+        let (output, starts) = self.backpatch_and_emit()?;
+
+        // Ops that emit nothing, like labels, are positioned at the next op
+        // that does.
+        let mut positions = vec![None; ops.len()];
+        for (origin, start) in self.origins.iter().zip(starts) {
+            positions[*origin].get_or_insert(start);
         }
 
-        let output = self.backpatch_and_emit()?;
+        let mut next = output.len();
+        self.positions = positions
+            .into_iter()
+            .rev()
+            .map(|position| {
+                next = position.unwrap_or(next);
+                next
+            })
+            .collect();
+        self.positions.reverse();
+
         self.ready.clear();
+        self.origins.clear();
+        self.spans.clear();
+        self.span = None;
         Ok(output)
     }
 
+    /// The position of each op passed to the most recent call to
+    /// [`Assembler::assemble`], in the code it returned.
+    pub fn positions(&self) -> &[usize] {
+        &self.positions
+    }
+
+    /// Iterate over every declared label and its position, counted from the
+    /// start of the code returned by the first call to
+    /// [`Assembler::assemble`].
+    ///
+    /// Unlike [`Assembler::positions`], positions accumulate across calls, so
+    /// a label declared by a later call is offset by the length of all the
+    /// code returned before it.
+    pub fn labels(&self) -> impl Iterator<Item = (&str, usize)> + '_ {
+        self.declared_labels
+            .iter()
+            .filter_map(|(name, def)| def.map(|d| (name.as_str(), d.position)))
+    }
+
     /// Pre-define macros, via `AbstractOp`, into the `Assembler`.
     ///
     /// This is used to define macros that are used in the same scope.
@@ -298,12 +471,16 @@ impl Assembler {
     where
         O: Into<RawOp> + Clone,
     {
-        for op in ops {
+        for (index, op) in ops.iter().enumerate() {
             let rop = op.clone().into();
             if let RawOp::Op(AbstractOp::MacroDefinition(ref defn)) = rop {
                 match self.declared_macros.entry(defn.name().to_owned()) {
                     hash_map::Entry::Occupied(_) => {
-                        return error::DuplicateMacro { name: defn.name() }.fail()
+                        return error::DuplicateMacro {
+                            name: defn.name(),
+                            span: self.spans.get(index).cloned(),
+                        }
+                        .fail()
                     }
                     hash_map::Entry::Vacant(v) => {
                         v.insert(defn.to_owned());
@@ -325,7 +502,7 @@ impl Assembler {
 
         match rop {
             RawOp::Op(AbstractOp::Label(label)) => {
-                self.undeclared_labels.retain(|l| *l != label);
+                self.undeclared_labels.retain(|l, _| *l != label);
 
                 let old = self
                     .declared_labels
@@ -357,6 +534,7 @@ impl Assembler {
                             expr: op.expr().unwrap().clone(),
                             value,
                             spec,
+                            span: self.span.clone(),
                         }
                         .fail()
                     }
@@ -364,6 +542,7 @@ impl Assembler {
                         return error::ExpressionNegative {
                             expr: op.expr().unwrap().clone(),
                             value,
+                            span: self.span.clone(),
                         }
                         .fail()
                     }
@@ -390,15 +569,31 @@ impl Assembler {
                             self.concrete_len += op.size().unwrap();
                         }
 
-                        self.undeclared_labels.extend(labels);
+                        for label in labels {
+                            self.undeclared_labels
+                                .entry(label)
+                                .or_insert(self.span.clone());
+                        }
                         self.ready.push(rop.clone());
                     }
                     Err(ops::Error::ContextIncomplete {
                         source: UnknownMacro { name, .. },
-                    }) => return error::UndeclaredInstructionMacro { name }.fail(),
+                    }) => {
+                        return error::UndeclaredInstructionMacro {
+                            name,
+                            span: self.span.clone(),
+                        }
+                        .fail()
+                    }
                     Err(ops::Error::ContextIncomplete {
                         source: UndefinedVariable { name, .. },
-                    }) => return error::UndeclaredVariableMacro { var: name }.fail(),
+                    }) => {
+                        return error::UndeclaredVariableMacro {
+                            var: name,
+                            span: self.span.clone(),
+                        }
+                        .fail()
+                    }
                 }
             }
             RawOp::Raw(raw) => {
@@ -462,29 +657,31 @@ impl Assembler {
     /// known at this stage. This function recalculates the size of each push operation based on the
     /// final resolved values of labels and expressions. If a push operation requires more space than
     /// initially estimated, the function adjusts the code accordingly.
-    fn backpatch_and_emit(&mut self) -> Result<Vec<u8>, Error> {
-        if !self.undeclared_labels.is_empty() {
+    ///
+    /// Returns the code, and the position of each entry of `ready` in it.
+    fn backpatch_and_emit(&mut self) -> Result<(Vec<u8>, Vec<usize>), Error> {
+        if let Some(span) = self.undeclared_labels.values().next() {
             return error::UndeclaredLabels {
                 labels: self
                     .undeclared_labels
-                    .iter()
+                    .keys()
                     .map(|l| l.to_owned())
                     .collect::<Vec<String>>(),
+                span: span.clone(),
             }
             .fail();
         }
         self.backpatch_labels()?;
-        let output = match self.emit_bytecode() {
-            Ok(value) => value,
-            Err(value) => return value,
-        };
-
-        Ok(output)
+        self.emit_bytecode()
     }
 
-    fn emit_bytecode(&mut self) -> Result<Vec<u8>, Result<Vec<u8>, Error>> {
+    fn emit_bytecode(&mut self) -> Result<(Vec<u8>, Vec<usize>), Error> {
         let mut output = Vec::new();
-        for op in self.ready.iter() {
+        let mut starts = Vec::with_capacity(self.ready.len());
+        for (op, origin) in self.ready.iter().zip(&self.origins) {
+            starts.push(output.len());
+            let span = self.spans.get(*origin).cloned();
+
             let op = match op {
                 RawOp::Op(ref op) => op,
                 RawOp::Raw(raw) => {
@@ -502,25 +699,26 @@ impl Assembler {
                 Err(ops::Error::ContextIncomplete {
                     source: UnknownLabel { .. },
                 }) => {
-                    return Err(error::UndeclaredLabels {
-                        labels: self.undeclared_labels.iter().cloned().collect::<Vec<_>>(),
+                    return error::UndeclaredLabels {
+                        labels: self.undeclared_labels.keys().cloned().collect::<Vec<_>>(),
+                        span,
                     }
-                    .fail());
+                    .fail();
                 }
                 Err(ops::Error::ContextIncomplete {
                     source: UnknownMacro { name, .. },
                 }) => {
-                    return Err(error::UndeclaredInstructionMacro { name }.fail());
+                    return error::UndeclaredInstructionMacro { name, span }.fail();
                 }
                 Err(ops::Error::ContextIncomplete {
                     source: UndefinedVariable { name, .. },
                 }) => {
-                    return Err(error::UndeclaredVariableMacro { var: name }.fail());
+                    return error::UndeclaredVariableMacro { var: name, span }.fail();
                 }
                 Err(_) => unreachable!("all ops should be concretizable"),
             }
         }
-        Ok(output)
+        Ok((output, starts))
     }
 
     fn declare_label(&mut self, rop: &RawOp) -> Result<(), Error> {
@@ -528,6 +726,7 @@ impl Assembler {
             if self.declared_labels.contains_key(label) {
                 return error::DuplicateLabel {
                     label: label.to_owned(),
+                    span: self.span.clone(),
                 }
                 .fail();
             }
@@ -566,6 +765,7 @@ impl Assembler {
                             if old.is_some() {
                                 return error::DuplicateLabel {
                                     label: label.to_string(),
+                                    span: self.span.clone(),
                                 }
                                 .fail();
                             }
@@ -598,8 +798,91 @@ impl Assembler {
                 }
                 Ok(Some(self.concrete_len))
             }
-            _ => error::UndeclaredInstructionMacro { name }.fail(),
+            _ => error::UndeclaredInstructionMacro {
+                name,
+                span: self.span.clone(),
+            }
+            .fail(),
+        }
+    }
+}
+
+/// Assembles source code one chunk at a time, keeping labels and macros
+/// defined by earlier chunks visible to later ones.
+///
+/// Each call to [`IncrementalAssembler::feed`] returns only the code emitted
+/// for that chunk, which directly follows the code returned by the previous
+/// call. A label must be defined in the same chunk where it is used, or in an
+/// earlier one. Directives that read files (`%import`, `%include`, and
+/// `%include_hex`) aren't supported.
+///
+/// ## Example
+///
+/// ```rust
+/// use etk_asm::asm::IncrementalAssembler;
+/// # use etk_asm::AssembleError;
+/// #
+/// # use hex_literal::hex;
+/// let mut asm = IncrementalAssembler::new();
+///
+/// let first = asm.feed("start:\njumpdest")?;
+/// let second = asm.feed("push1 start\njump")?;
+/// # assert_eq!(first, hex!("5b"));
+/// # assert_eq!(second, hex!("600056"));
+/// # Result::<(), AssembleError>::Ok(())
+/// ```
+#[derive(Debug, Default)]
+pub struct IncrementalAssembler {
+    asm: Assembler,
+}
+
+impl IncrementalAssembler {
+    /// Create a new `IncrementalAssembler`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Assemble another chunk of source code.
+    ///
+    /// Returns the code emitted for `src`. If an error occurs, the state of
+    /// the assembler is left as it was before the call.
+    pub fn feed(&mut self, src: &str) -> Result<Vec<u8>, AssembleError> {
+        let mut ops = Vec::new();
+        let mut spans = Vec::new();
+        for (node, span) in parse_asm(src).context(error::Parse)? {
+            let directive = match node {
+                Node::Op(op) => {
+                    ops.push(RawOp::Op(op));
+                    spans.push(span);
+                    continue;
+                }
+                Node::Import(_) => "%import",
+                Node::Include(_) => "%include",
+                Node::IncludeHex(_) => "%include_hex",
+            };
+
+            return error::UnsupportedDirective {
+                directive,
+                span: Some(span),
+            }
+            .fail();
         }
+
+        let mut asm = self.asm.clone();
+        let output = asm.assemble_spanned(&ops, spans)?;
+
+        // Pushes from this chunk have been emitted, so their sizes are final.
+        asm.variable_sized_push.clear();
+
+        self.asm = asm;
+        Ok(output)
+    }
+
+    /// Iterate over every declared label and its position, counted from the
+    /// start of the code returned by the first call to
+    /// [`IncrementalAssembler::feed`].
+    pub fn labels(&self) -> impl Iterator<Item = (&str, usize)> + '_ {
+        self.asm.labels()
     }
 }
 
@@ -744,6 +1027,43 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn assemble_positions() -> Result<(), Error> {
+        let mut asm = Assembler::new();
+        let code: Vec<RawOp> = vec![
+            AbstractOp::Push(Imm::with_label("auto")).into(),
+            RawOp::Raw(vec![0x00; 0x100]),
+            AbstractOp::Label("auto".into()).into(),
+            AbstractOp::new(JumpDest).into(),
+            AbstractOp::Label("end".into()).into(),
+        ];
+        let result = asm.assemble(&code)?;
+        assert_eq!(result.len(), 0x104);
+        assert_eq!(asm.positions(), [0, 3, 0x103, 0x103, 0x104]);
+        Ok(())
+    }
+
+    #[test]
+    fn assemble_labels_across_calls() -> Result<(), Error> {
+        let mut asm = Assembler::new();
+
+        let first = asm.assemble(&[AbstractOp::new(GetPc), AbstractOp::new(GetPc)])?;
+        assert_eq!(first, hex!("5858"));
+
+        let second = asm.assemble(&[
+            AbstractOp::new(GetPc),
+            AbstractOp::Label("later".into()),
+            AbstractOp::new(JumpDest),
+        ])?;
+        assert_eq!(second, hex!("585b"));
+
+        // Positions restart with each call, but labels don't.
+        assert_eq!(asm.positions(), [0, 1, 1]);
+        assert_eq!(asm.labels().collect::<Vec<_>>(), [("later", 3)]);
+
+        Ok(())
+    }
+
     #[test]
     fn assemble_variable_push1_reuse() -> Result<(), Error> {
         let mut asm = Assembler::new();
@@ -1537,4 +1857,70 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn incremental_label_from_earlier_chunk() -> Result<(), Error> {
+        let mut asm = IncrementalAssembler::new();
+
+        let first = asm.feed("pc\nstart:\njumpdest\n")?;
+        assert_eq!(first, hex!("585b"));
+
+        let second = asm.feed("%push(start)\njump\nend:\njumpdest\n")?;
+        assert_eq!(second, hex!("6001565b"));
+
+        let labels: Vec<_> = asm.labels().collect();
+        assert_eq!(labels, [("start", 1), ("end", 5)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn incremental_variable_push_in_earlier_chunk() -> Result<(), Error> {
+        let mut asm = IncrementalAssembler::new();
+
+        let first = asm.feed("%push(lbl)\nlbl:\njumpdest\n")?;
+        assert_eq!(first, hex!("60025b"));
+
+        let second = asm.feed("push1 lbl\n")?;
+        assert_eq!(second, hex!("6002"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn incremental_error_keeps_state() -> Result<(), Error> {
+        let mut asm = IncrementalAssembler::new();
+        asm.feed("lbl:\njumpdest\n")?;
+
+        let err = asm.feed("other:\npush1 missing\n").unwrap_err();
+        assert_matches!(err, Error::UndeclaredLabels { .. });
+
+        let err = asm.feed("lbl:\n").unwrap_err();
+        assert_matches!(err, Error::DuplicateLabel { .. });
+
+        let code = asm.feed("other:\npush1 lbl\n")?;
+        assert_eq!(code, hex!("6000"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn incremental_unsupported_directive() {
+        let mut asm = IncrementalAssembler::new();
+        let err = asm.feed(r#"%include("foo.etk")"#).unwrap_err();
+        assert_matches!(err, Error::UnsupportedDirective { directive, .. } if directive == "%include");
+    }
+
+    #[test]
+    fn incremental_parse_error() {
+        let src = "jumpdest\npush1 0x0100\n";
+
+        let mut asm = IncrementalAssembler::new();
+        let err = asm.feed(src).unwrap_err();
+
+        assert_matches!(err, Error::Parse { .. });
+        assert_eq!(err.code(), "E100");
+        assert_eq!(err.span().map(|s| &src[s]), Some("0x0100"));
+        assert!(err.to_string().starts_with("parsing failed: "));
+    }
 }
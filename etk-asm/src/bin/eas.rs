@@ -1,7 +1,9 @@
 use etk_cli::errors::WithSources;
 use etk_cli::io::HexWrite;
 
-use etk_asm::ingest::{Error, Ingest};
+use etk_asm::ingest::{self, Ingest};
+
+use snafu::{Backtrace, ResultExt, Snafu};
 
 use std::fs::File;
 use std::io::prelude::*;
@@ -9,6 +11,23 @@ use std::path::PathBuf;
 
 use clap::StructOpt;
 
+#[derive(Debug, Snafu)]
+#[snafu(context(suffix(false)))]
+enum Error {
+    #[snafu(context(false))]
+    Ingest {
+        #[snafu(backtrace)]
+        source: ingest::Error,
+    },
+
+    #[snafu(display("unable to write artifact `{}`", path.display()))]
+    WriteArtifact {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+}
+
 #[derive(Debug, StructOpt)]
 #[structopt(name = "eas")]
 struct Opt {
@@ -16,6 +35,12 @@ struct Opt {
     input: PathBuf,
     #[structopt(parse(from_os_str))]
     out: Option<PathBuf>,
+    #[structopt(
+        long = "artifact",
+        parse(from_os_str),
+        help = "path to write the assembled code with its labels and sources"
+    )]
+    artifact: Option<PathBuf>,
 }
 
 fn create(path: PathBuf) -> File {
@@ -45,9 +70,17 @@ fn run() -> Result<(), Error> {
 
     let hex_out = HexWrite::new(&mut out);
 
-    let mut ingest = Ingest::new(hex_out);
+    let mut ingest = match opt.artifact {
+        Some(_) => Ingest::with_artifact(hex_out),
+        None => Ingest::new(hex_out),
+    };
     ingest.ingest_file(opt.input)?;
 
+    if let (Some(path), Some(artifact)) = (opt.artifact, ingest.artifact()) {
+        let file = File::create(&path).context(WriteArtifact { path: path.clone() })?;
+        artifact.write_to(file).context(WriteArtifact { path })?;
+    }
+
     out.write_all(b"\n").unwrap();
 
     Ok(())
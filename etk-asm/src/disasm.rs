@@ -22,10 +22,36 @@ mod error {
             /// The location of the error.
             backtrace: Backtrace,
         },
+
+        /// The requested offset falls inside an instruction.
+        #[snafu(display("offset {} is not the start of an instruction", offset))]
+        #[non_exhaustive]
+        NotBoundary {
+            /// The requested offset.
+            offset: usize,
+
+            /// The location of the error.
+            backtrace: Backtrace,
+        },
+
+        /// The requested offset is past the end of the input.
+        #[snafu(display("offset {} is past the end of the input ({} bytes)", offset, len))]
+        #[non_exhaustive]
+        OutOfRange {
+            /// The requested offset.
+            offset: usize,
+
+            /// The length of the input.
+            len: usize,
+
+            /// The location of the error.
+            backtrace: Backtrace,
+        },
     }
 }
 
 use etk_ops::cancun::Op;
+use etk_ops::Immediates;
 
 pub use self::error::Error;
 
@@ -34,9 +60,12 @@ use snafu::ensure;
 use std::collections::VecDeque;
 use std::fmt;
 use std::io::{self, Write};
+use std::ops::Add;
 
 /// An item with its location within a stream of bytes.
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+///
+/// Ordering compares `offset` first, then `item`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct Offset<T> {
     /// The location within a stream of bytes of a particular item.
     pub offset: usize,
@@ -52,6 +81,26 @@ impl<T> Offset<T> {
     }
 }
 
+impl<T> Offset<Op<T>>
+where
+    T: Immediates + ?Sized,
+{
+    /// The location immediately after `item`, where the next instruction
+    /// begins.
+    pub fn next(&self) -> usize {
+        self.offset + self.item.size()
+    }
+}
+
+impl<T> Add<usize> for Offset<T> {
+    type Output = Self;
+
+    /// Move `item` forward by `rhs` bytes.
+    fn add(self, rhs: usize) -> Self::Output {
+        Self::new(self.offset + rhs, self.item)
+    }
+}
+
 impl<T> fmt::Display for Offset<T>
 where
     T: fmt::Display,
@@ -61,6 +110,33 @@ where
     }
 }
 
+/// Interpret `imm`, such as the immediate of a push, as a big-endian unsigned
+/// integer.
+///
+/// Returns `None` if the value doesn't fit in a `usize`.
+///
+/// ## Example
+/// ```rust
+/// use etk_asm::disasm::immediate_to_usize;
+///
+/// assert_eq!(immediate_to_usize(&[0x00, 0x01, 0x02]), Some(0x0102));
+/// assert_eq!(immediate_to_usize(&[0xff; 32]), None);
+/// ```
+pub fn immediate_to_usize(imm: &[u8]) -> Option<usize> {
+    let mut imm = imm;
+
+    // Strip leading zeros.
+    while !imm.is_empty() && imm[0] == 0 {
+        imm = &imm[1..];
+    }
+
+    let mut be_bytes = [0u8; std::mem::size_of::<usize>()];
+    let start = be_bytes.len().checked_sub(imm.len())?;
+    be_bytes[start..].copy_from_slice(imm);
+
+    Some(usize::from_be_bytes(be_bytes))
+}
+
 /// A [`std::iter::Iterator`] over the [`Op<[u8]>`] produced by disassembling
 /// a stream of bytes.
 #[derive(Debug)]
@@ -156,14 +232,100 @@ impl Disassembler {
     }
 }
 
+/// Disassemble up to `max_instrs` instructions from `bytes`, beginning at the
+/// instruction that starts at `start`.
+///
+/// Returns an error if `start` falls inside an instruction (for example in
+/// the immediate of a push) or past the end of `bytes`, or if an instruction
+/// before `start` or among those requested is cut off.
+///
+/// ## Example
+/// ```rust
+/// use etk_ops::cancun::{Op, Push1, Stop};
+/// use etk_asm::disasm::{disassemble_range, Offset};
+///
+/// // push1 0x01, push1 0x02, stop
+/// let input = [0x60, 0x01, 0x60, 0x02, 0x00];
+///
+/// let actual = disassemble_range(&input, 2, 1).unwrap();
+/// # let expected = [Offset::new(2, Op::from(Push1([0x02])))];
+/// # assert_eq!(expected, actual.as_slice());
+///
+/// assert!(disassemble_range(&input, 1, 1).is_err());
+/// ```
+pub fn disassemble_range(
+    bytes: &[u8],
+    start: usize,
+    max_instrs: usize,
+) -> Result<Vec<Offset<Op<[u8]>>>, Error> {
+    let mut offset = 0;
+    while offset < start && offset < bytes.len() {
+        let next = Offset::new(offset, Op::<()>::from(bytes[offset])).next();
+
+        ensure!(
+            next <= bytes.len(),
+            error::Truncated {
+                remaining: Offset::new(offset, bytes[offset..].to_vec()),
+            }
+        );
+
+        offset = next;
+    }
+
+    ensure!(
+        offset >= start,
+        error::OutOfRange {
+            offset: start,
+            len: bytes.len(),
+        }
+    );
+    ensure!(offset == start, error::NotBoundary { offset: start });
+
+    let mut dasm = Disassembler {
+        buffer: bytes[start..].iter().copied().collect(),
+        offset: start,
+    };
+
+    let ops: Vec<_> = dasm.ops().take(max_instrs).collect();
+
+    // Stopping early means the input ran out, possibly partway through an
+    // instruction.
+    if ops.len() < max_instrs {
+        dasm.finish()?;
+    }
+
+    Ok(ops)
+}
+
 #[cfg(test)]
 mod tests {
+    use assert_matches::assert_matches;
+
     use etk_ops::cancun::*;
 
     use hex_literal::hex;
 
     use super::*;
 
+    #[test]
+    fn offset_ordering() {
+        let first = Offset::new(0, Op::<[u8]>::from(Stop));
+        let second = Offset::new(1, Op::<[u8]>::from(Stop));
+
+        assert!(first < second);
+        assert!(Offset::new(1, Op::<[u8]>::from(Stop)) < Offset::new(1, Op::from(Add)));
+        assert_eq!(first + 1, second);
+    }
+
+    #[test]
+    fn offset_next() {
+        let push = Offset::new(3, Op::<[u8]>::from(Push2(hex!("0102"))));
+        assert_eq!(push.next(), 6);
+
+        let stop = Offset::new(push.next(), Op::<[u8]>::from(Stop));
+        assert_eq!(stop.next(), 7);
+    }
+
     #[test]
     fn empty() {
         let mut dasm = Disassembler::new();
@@ -215,4 +377,62 @@ mod tests {
         assert_eq!(expected, actual.as_slice());
         dasm.finish().unwrap();
     }
+
+    #[test]
+    fn range_partway() {
+        // push1 0x01, push2 0x0203, add, pc, stop
+        let input = hex!("6001 610203 01 58 00");
+        let expected = [
+            Offset::new(2, Op::from(Push2(hex!("0203")))),
+            Offset::new(5, Op::from(Add)),
+            Offset::new(6, Op::from(GetPc)),
+        ];
+
+        let actual = disassemble_range(&input, 2, 3).unwrap();
+        assert_eq!(expected, actual.as_slice());
+    }
+
+    #[test]
+    fn range_past_end() {
+        let input = hex!("6001 00");
+        let actual = disassemble_range(&input, 2, 10).unwrap();
+        assert_eq!([Offset::new(2, Op::from(Stop))], actual.as_slice());
+
+        assert!(disassemble_range(&input, 3, 10).unwrap().is_empty());
+        assert_matches!(
+            disassemble_range(&input, 4, 10),
+            Err(Error::OutOfRange {
+                offset: 4,
+                len: 3,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn range_inside_push() {
+        let input = hex!("7f 5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b 00");
+        assert_matches!(
+            disassemble_range(&input, 1, 1),
+            Err(Error::NotBoundary { offset: 1, .. })
+        );
+    }
+
+    #[test]
+    fn range_truncated() {
+        let input = hex!("00 6101");
+        assert_matches!(
+            disassemble_range(&input, 1, 1),
+            Err(Error::Truncated { remaining, .. }) if remaining == Offset::new(1, vec![0x61, 0x01])
+        );
+    }
+
+    #[test]
+    fn range_start_after_truncated() {
+        let input = hex!("6101");
+        assert_matches!(
+            disassemble_range(&input, 3, 1),
+            Err(Error::Truncated { remaining, .. }) if remaining == Offset::new(0, vec![0x61, 0x01])
+        );
+    }
 }
@@ -7,6 +7,7 @@ mod error {
 
     use snafu::{Backtrace, Snafu};
 
+    use std::ops::Range;
     use std::path::PathBuf;
 
     /// Errors that may arise during the assembly process.
@@ -63,13 +64,18 @@ mod error {
         },
 
         /// An error that occurred while assembling a file.
-        #[snafu(context(false))]
         #[non_exhaustive]
         #[snafu(display("assembling failed"))]
         Assemble {
             /// The underlying source of this error.
             #[snafu(backtrace)]
             source: AssembleError,
+
+            /// The file containing the offending instruction, and the
+            /// instruction's byte range in that file, if known.
+            ///
+            /// Boxed to keep [`Error`] small.
+            file_span: Option<Box<(PathBuf, Range<usize>)>>,
         },
 
         /// An included fail failed to parse as hexadecimal.
@@ -96,6 +102,7 @@ mod error {
     }
 }
 
+use crate::artifact::{Artifact, SourceRange};
 use crate::asm::{Assembler, RawOp};
 use crate::ast::Node;
 use crate::parse::parse_asm;
@@ -104,10 +111,49 @@ pub use self::error::Error;
 
 use snafu::{ensure, ResultExt};
 
+use std::collections::HashMap;
 use std::fs::{read_to_string, File};
 use std::io::{self, Read, Write};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
+impl Error {
+    /// Byte range into the source code where the error occurred, if known.
+    ///
+    /// For [`Error::Parse`], the range is into the file at `path`. For
+    /// [`Error::Assemble`], it's into the file given by [`Error::path`].
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            Self::Parse { source, .. } => Some(source.span()),
+            Self::Assemble { file_span, .. } => file_span.as_ref().map(|l| l.1.clone()),
+            _ => None,
+        }
+    }
+
+    /// The file where the error occurred, if known.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Parse { path, .. } => Some(path),
+            Self::Assemble { file_span, .. } => file_span.as_ref().map(|l| l.0.as_path()),
+            Self::Io { path, .. } => path.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// A short code identifying the kind of error, which doesn't change
+    /// between releases.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::DirectoryTraversal { .. } => "E300",
+            Self::Io { .. } => "E301",
+            Self::Parse { source, .. } => source.code(),
+            Self::Assemble { source, .. } => source.code(),
+            Self::InvalidHex { .. } => "E302",
+            Self::RecursionLimit { .. } => "E303",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct Root {
     original: PathBuf,
@@ -184,6 +230,11 @@ impl Root {
 struct Program {
     root: Option<Root>,
     sources: Vec<PathBuf>,
+
+    /// Every file parsed so far, with the offset its spans were shifted by so
+    /// that spans from different files never overlap.
+    files: Vec<(usize, PathBuf)>,
+    next_base: usize,
 }
 
 impl Program {
@@ -191,9 +242,45 @@ impl Program {
         Self {
             root: Root::new(path.clone()).ok(),
             sources: vec![path],
+            files: Vec::new(),
+            next_base: 0,
         }
     }
 
+    /// Record that `src` was read from `path`, returning the offset to add to
+    /// its spans.
+    fn add_file(&mut self, path: PathBuf, src: &str) -> usize {
+        let base = self.next_base;
+        self.files.push((base, path));
+        self.next_base += src.len() + 1;
+        base
+    }
+
+    /// Express `path` relative to the root, so it doesn't depend on the
+    /// working directory.
+    fn relative(&self, path: &Path) -> PathBuf {
+        let root = match self.root {
+            Some(ref root) => root,
+            None => return path.to_owned(),
+        };
+
+        std::fs::canonicalize(path)
+            .ok()
+            .and_then(|p| p.strip_prefix(&root.canonicalized).ok().map(Path::to_owned))
+            .unwrap_or_else(|| path.to_owned())
+    }
+
+    /// Map a shifted span back to the file it came from.
+    fn locate(&self, span: Range<usize>) -> (PathBuf, Range<usize>) {
+        let (base, path) = self
+            .files
+            .iter()
+            .rev()
+            .find(|(base, _)| *base <= span.start)
+            .expect("span from an unrecorded file");
+        (path.clone(), (span.start - base)..(span.end - base))
+    }
+
     fn push_path(&mut self, path: &PathBuf) -> Result<PathBuf, Error> {
         ensure!(self.sources.len() <= 255, error::RecursionLimit);
 
@@ -262,12 +349,31 @@ impl Program {
 #[derive(Debug)]
 pub struct Ingest<W> {
     output: W,
+    artifact: Option<Artifact>,
 }
 
 impl<W> Ingest<W> {
     /// Make a new `Ingest` that writes assembled bytes to `output`.
     pub fn new(output: W) -> Self {
-        Self { output }
+        Self {
+            output,
+            artifact: None,
+        }
+    }
+
+    /// Make a new `Ingest` that writes assembled bytes to `output`, and also
+    /// collects them into an [`Artifact`] with their labels and sources.
+    pub fn with_artifact(output: W) -> Self {
+        Self {
+            output,
+            artifact: Some(Artifact::default()),
+        }
+    }
+
+    /// The [`Artifact`] describing everything assembled so far, if `self` was
+    /// created with [`Ingest::with_artifact`].
+    pub fn artifact(&self) -> Option<&Artifact> {
+        self.artifact.as_ref()
     }
 }
 
@@ -302,12 +408,29 @@ where
     where
         P: Into<PathBuf>,
     {
-        let mut program = Program::new(path.into());
-        let nodes = self.preprocess(&mut program, src)?;
-        let mut asm = Assembler::new();
-        let raw = asm.assemble(&nodes)?;
+        let path = path.into();
+        let mut program = Program::new(path);
+        let items = self.preprocess(&mut program, src)?;
+        let scope = assemble(&program, items)?;
+
+        if let Some(ref mut artifact) = self.artifact {
+            let offset = artifact.code.len();
 
-        self.output.write_all(&raw).context(error::Io {
+            artifact.code.extend_from_slice(&scope.code);
+
+            for source in scope.sources {
+                artifact.sources.push(SourceRange {
+                    offset: offset + source.offset,
+                    ..source
+                });
+            }
+
+            for (name, position) in scope.labels {
+                artifact.labels.insert(name, offset + position);
+            }
+        }
+
+        self.output.write_all(&scope.code).context(error::Io {
             message: "writing output",
             path: None,
         })?;
@@ -315,59 +438,208 @@ where
         Ok(())
     }
 
-    fn preprocess(&mut self, program: &mut Program, src: &str) -> Result<Vec<RawOp>, Error> {
-        let nodes = parse_asm(src).with_context(|_| error::Parse {
-            path: program.sources.last().unwrap().clone(),
-        })?;
-        let mut raws = Vec::new();
-        for node in nodes {
+    fn preprocess(&mut self, program: &mut Program, src: &str) -> Result<Vec<Item>, Error> {
+        let path = program.sources.last().unwrap().clone();
+        let nodes = parse_asm(src).with_context(|_| error::Parse { path: path.clone() })?;
+        let base = program.add_file(path.clone(), src);
+        let mut items = Vec::new();
+        for (node, span) in nodes {
+            let span = (base + span.start)..(base + span.end);
             match node {
                 Node::Op(op) => {
-                    raws.push(RawOp::Op(op));
+                    items.push(Item {
+                        op: RawOp::Op(op),
+                        origin: Origin::File(path.clone()),
+                        span,
+                    });
                 }
                 Node::Import(imp_path) => {
-                    let new_raws = self.resolve_and_ingest(program, imp_path)?;
-                    raws.extend(new_raws);
+                    let (_, new_items) = self.resolve_and_ingest(program, imp_path)?;
+                    items.extend(new_items);
                 }
                 Node::Include(inc_path) => {
-                    let inc_raws = self.resolve_and_ingest(program, inc_path)?;
-                    raws.push(RawOp::Scope(inc_raws));
+                    let (source, scope) = self.include(program, inc_path)?;
+                    let origin = Origin::Include {
+                        path: source,
+                        labels: scope.labels,
+                        sources: scope.sources,
+                    };
+                    items.push(Item {
+                        op: RawOp::Raw(scope.code),
+                        origin,
+                        span,
+                    });
                 }
                 Node::IncludeHex(hex_path) => {
-                    let source = program.resolve_path(&hex_path)?;
-                    let file = std::fs::read_to_string(&source).with_context(|_| error::Io {
-                        message: "reading hex include",
-                        path: hex_path.to_owned(),
-                    })?;
-
-                    let raw = hex::decode(file.trim())
-                        .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
-                        .context(error::InvalidHex {
-                            path: hex_path.to_owned(),
-                        })?;
-
-                    raws.push(RawOp::Raw(raw))
+                    let (source, raw) = self.include_hex(program, hex_path)?;
+                    items.push(Item {
+                        op: RawOp::Raw(raw),
+                        origin: Origin::File(source),
+                        span,
+                    });
                 }
             }
         }
 
-        Ok(raws)
+        Ok(items)
+    }
+
+    fn include(&mut self, program: &mut Program, path: PathBuf) -> Result<(PathBuf, Scope), Error> {
+        let (source, items) = self.resolve_and_ingest(program, path)?;
+        let scope = assemble(program, items)?;
+        Ok((source, scope))
+    }
+
+    fn include_hex(
+        &mut self,
+        program: &mut Program,
+        hex_path: PathBuf,
+    ) -> Result<(PathBuf, Vec<u8>), Error> {
+        let source = program.resolve_path(&hex_path)?;
+        let file = std::fs::read_to_string(&source).with_context(|_| error::Io {
+            message: "reading hex include",
+            path: hex_path.to_owned(),
+        })?;
+
+        let raw = hex::decode(file.trim())
+            .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
+            .context(error::InvalidHex {
+                path: hex_path.to_owned(),
+            })?;
+
+        Ok((source, raw))
     }
 
     fn resolve_and_ingest(
         &mut self,
         program: &mut Program,
         path: PathBuf,
-    ) -> Result<Vec<RawOp>, Error> {
+    ) -> Result<(PathBuf, Vec<Item>), Error> {
         let source = program.push_path(&path)?;
-        let code = read_to_string(source).with_context(|_| error::Io {
+        let code = read_to_string(&source).with_context(|_| error::Io {
             message: "reading file before parsing",
             path: path.to_owned(),
         })?;
-        let new_raws = self.preprocess(program, &code)?;
+        let new_items = self.preprocess(program, &code)?;
         program.pop_path();
-        Ok(new_raws)
+        Ok((source, new_items))
+    }
+}
+
+/// An op to be assembled, with where it came from.
+#[derive(Debug)]
+struct Item {
+    op: RawOp,
+    origin: Origin,
+
+    /// Location of the op in its file, shifted as described in [`Program`].
+    span: Range<usize>,
+}
+
+/// Where an op to be assembled came from.
+#[derive(Debug)]
+enum Origin {
+    /// The op was read from the file at the given path.
+    File(PathBuf),
+
+    /// The op is the code of an included file, already assembled in its own
+    /// scope.
+    Include {
+        path: PathBuf,
+        labels: Vec<(String, usize)>,
+        sources: Vec<SourceRange>,
+    },
+}
+
+/// Code assembled in a single scope, with its labels and source map.
+#[derive(Debug)]
+struct Scope {
+    code: Vec<u8>,
+    labels: Vec<(String, usize)>,
+    sources: Vec<SourceRange>,
+}
+
+fn assemble(program: &Program, items: Vec<Item>) -> Result<Scope, Error> {
+    let mut ops = Vec::with_capacity(items.len());
+    let mut origins = Vec::with_capacity(items.len());
+    let mut spans = Vec::with_capacity(items.len());
+
+    for item in items {
+        ops.push(item.op);
+        origins.push(item.origin);
+        spans.push(item.span);
+    }
+
+    let mut asm = Assembler::new();
+    let code = match asm.assemble_spanned(&ops, spans) {
+        Ok(code) => code,
+        Err(source) => {
+            let file_span = source.span().map(|span| Box::new(program.locate(span)));
+            return Err(source).context(error::Assemble { file_span });
+        }
+    };
+
+    let mut labels: Vec<_> = asm
+        .labels()
+        .map(|(name, position)| (name.to_owned(), position))
+        .collect();
+    let mut sources = Vec::new();
+
+    // How many times each file has been included so far, to keep the labels
+    // of each copy distinct.
+    let mut included = HashMap::<PathBuf, usize>::new();
+
+    let positions = asm.positions();
+    let ends = positions.iter().skip(1).copied().chain(Some(code.len()));
+
+    for ((origin, &start), end) in origins.into_iter().zip(positions).zip(ends) {
+        match origin {
+            Origin::File(path) => push_source(&mut sources, start, end - start, path),
+            Origin::Include {
+                path,
+                labels: inner_labels,
+                sources: inner_sources,
+            } => {
+                let count = included.entry(path.clone()).or_default();
+                *count += 1;
+
+                let relative = program.relative(&path);
+                let prefix = match *count {
+                    1 => relative.display().to_string(),
+                    n => format!("{}#{}", relative.display(), n),
+                };
+
+                for (name, position) in inner_labels {
+                    labels.push((format!("{}:{}", prefix, name), start + position));
+                }
+
+                for source in inner_sources {
+                    push_source(&mut sources, start + source.offset, source.len, source.path);
+                }
+            }
+        }
+    }
+
+    Ok(Scope {
+        code,
+        labels,
+        sources,
+    })
+}
+
+fn push_source(sources: &mut Vec<SourceRange>, offset: usize, len: usize, path: PathBuf) {
+    if len == 0 {
+        return;
+    }
+
+    if let Some(last) = sources.last_mut() {
+        if last.path == path && last.offset + last.len == offset {
+            last.len += len;
+            return;
+        }
     }
+
+    sources.push(SourceRange { offset, len, path });
 }
 
 #[cfg(test)]
@@ -471,7 +743,8 @@ mod tests {
         assert_matches!(
             err,
             Error::Assemble {
-                source: AsmError::DuplicateLabel { label, ..}
+                source: AsmError::DuplicateLabel { label, ..},
+                ..
             } if label == "a"
         );
     }
@@ -675,4 +948,69 @@ mod tests {
 
         assert_matches!(err, Error::RecursionLimit { .. });
     }
+
+    #[test]
+    fn ingest_parse_error_span() {
+        let text = "push1 0x01\npush1 0x0100\n";
+
+        let mut output = Vec::new();
+        let mut ingest = Ingest::new(&mut output);
+        let err = ingest.ingest("./main.etk", text).unwrap_err();
+
+        assert_matches!(err, Error::Parse { .. });
+        assert_eq!(err.code(), "E100");
+        assert_eq!(err.span().map(|s| &text[s]), Some("0x0100"));
+    }
+
+    #[test]
+    fn ingest_assemble_error_code() {
+        let text = "push1 missing";
+
+        let mut output = Vec::new();
+        let mut ingest = Ingest::new(&mut output);
+        let err = ingest.ingest("./main.etk", text).unwrap_err();
+
+        assert_eq!(err.code(), "E205");
+        assert_eq!(err.span().map(|s| &text[s]), Some("push1 missing"));
+    }
+
+    #[test]
+    fn ingest_assemble_error_span_in_import() {
+        let imported = "jumpdest\npush1 missing\n";
+        let (f, root) = new_file(imported);
+
+        let text = format!(
+            r#"
+                push1 1
+                %import("{}")
+            "#,
+            f.path().display(),
+        );
+
+        let mut output = Vec::new();
+        let mut ingest = Ingest::new(&mut output);
+        let err = ingest.ingest(root, &text).unwrap_err();
+
+        assert_matches!(err, Error::Assemble { .. });
+        assert!(err.path().unwrap().ends_with(f.path().file_name().unwrap()));
+        assert_eq!(err.span().map(|s| &imported[s]), Some("push1 missing"));
+    }
+
+    #[test]
+    fn ingest_duplicate_label_span() {
+        let text = "a:\njumpdest\na:\n";
+
+        let mut output = Vec::new();
+        let mut ingest = Ingest::new(&mut output);
+        let err = ingest.ingest("./main.etk", text).unwrap_err();
+
+        assert_matches!(
+            err,
+            Error::Assemble {
+                source: AsmError::DuplicateLabel { .. },
+                ..
+            }
+        );
+        assert_eq!(err.span(), Some(12..14));
+    }
 }
@@ -6,16 +6,23 @@
 //! The [`ingest`] module is high-level and similar to the command-line interface.
 //!
 //! The [`mod@asm`] module provides low-level access to the internals of the assembler.
+//!
+//! The [`artifact`] module bundles assembled code with its labels and sources.
+//!
+//! The [`validate`] module checks bytecode for structural problems.
 #![deny(unsafe_code)]
 #![deny(missing_docs)]
 #![deny(unreachable_pub)]
 #![deny(missing_debug_implementations)]
 
+pub mod artifact;
 pub mod asm;
 mod ast;
 pub mod disasm;
 pub mod ingest;
 pub mod ops;
 mod parse;
+pub mod validate;
 
+pub use self::asm::Error as AssembleError;
 pub use self::parse::error::ParseError;
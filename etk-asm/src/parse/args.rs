@@ -1,6 +1,7 @@
 use super::{error, ParseError, Rule};
 use pest::iterators::{Pair, Pairs};
 use snafu::{ensure, OptionExt};
+use std::ops::Range;
 use std::path::PathBuf;
 
 pub(super) trait FromPair: Sized {
@@ -9,7 +10,10 @@ pub(super) trait FromPair: Sized {
 
 impl FromPair for PathBuf {
     fn from_pair(pair: Pair<Rule>) -> Result<Self, ParseError> {
-        ensure!(pair.as_rule() == Rule::string, error::ArgumentType);
+        ensure!(
+            pair.as_rule() == Rule::string,
+            error::ArgumentType { span: span(&pair) }
+        );
 
         let txt = pair.as_str();
         if txt.contains('\\') {
@@ -33,16 +37,33 @@ impl FromPair for Label {
 
 pub(super) trait Signature {
     type Output;
-    fn parse_arguments(pairs: Pairs<Rule>) -> Result<Self::Output, ParseError>;
+
+    /// Parse the arguments of the macro invocation `pair`, where `invocation`
+    /// is the span of the whole invocation (including any leading `%`).
+    fn parse_arguments(
+        pair: Pair<Rule>,
+        invocation: Range<usize>,
+    ) -> Result<Self::Output, ParseError>;
+}
+
+pub(super) fn span(pair: &Pair<Rule>) -> Range<usize> {
+    let span = pair.as_span();
+    span.start()..span.end()
 }
 
-fn arg<T>(pairs: &mut Pairs<Rule>, expected: usize, got: &mut usize) -> Result<T, ParseError>
+fn arg<T>(
+    pairs: &mut Pairs<Rule>,
+    invocation: &Range<usize>,
+    expected: usize,
+    got: &mut usize,
+) -> Result<T, ParseError>
 where
     T: FromPair,
 {
     let pair = pairs.next().context(error::MissingArgument {
         got: *got,
         expected,
+        span: invocation.clone(),
     })?;
     *got += 1;
     T::from_pair(pair)
@@ -51,9 +72,13 @@ where
 impl Signature for () {
     type Output = Self;
 
-    fn parse_arguments(mut pairs: Pairs<Rule>) -> Result<Self, ParseError> {
-        match pairs.next() {
-            Some(_) => error::ExtraArgument { expected: 0usize }.fail(),
+    fn parse_arguments(pair: Pair<Rule>, _: Range<usize>) -> Result<Self, ParseError> {
+        match pair.into_inner().next() {
+            Some(extra) => error::ExtraArgument {
+                expected: 0usize,
+                span: span(&extra),
+            }
+            .fail(),
             None => Ok(()),
         }
     }
@@ -65,14 +90,20 @@ where
 {
     type Output = Self;
 
-    fn parse_arguments(mut pairs: Pairs<Rule>) -> Result<Self, ParseError> {
+    fn parse_arguments(pair: Pair<Rule>, invocation: Range<usize>) -> Result<Self, ParseError> {
         let expected = 1;
         let mut got = 0;
 
-        let result = (arg::<T>(&mut pairs, expected, &mut got)?,);
+        let mut pairs = pair.into_inner();
+
+        let result = (arg::<T>(&mut pairs, &invocation, expected, &mut got)?,);
 
         match pairs.next() {
-            Some(_) => error::ExtraArgument { expected }.fail(),
+            Some(extra) => error::ExtraArgument {
+                expected,
+                span: span(&extra),
+            }
+            .fail(),
             None => Ok(result),
         }
     }
@@ -1,7 +1,9 @@
-use pest::error::Error;
+use pest::error::{Error, InputLocation};
 
 use snafu::{Backtrace, IntoError, Snafu};
 
+use std::ops::Range;
+
 use super::Rule;
 
 /// Type for errors that may arise while parsing assembly source code.
@@ -13,6 +15,9 @@ pub enum ParseError {
     #[snafu(display("an immediate value was too large for the given opcode"))]
     #[non_exhaustive]
     ImmediateTooLarge {
+        /// Byte range of the immediate in the source.
+        span: Range<usize>,
+
         /// The location of the error.
         backtrace: Backtrace,
     },
@@ -24,6 +29,9 @@ pub enum ParseError {
         /// The underlying source of this error.
         source: Box<dyn std::error::Error>,
 
+        /// Byte range in the source where lexing failed.
+        span: Range<usize>,
+
         /// The location of this error.
         backtrace: Backtrace,
     },
@@ -38,6 +46,9 @@ pub enum ParseError {
         /// How many arguments were provided.
         got: usize,
 
+        /// Byte range of the macro invocation in the source.
+        span: Range<usize>,
+
         /// Location of the error.
         backtrace: Backtrace,
     },
@@ -49,6 +60,9 @@ pub enum ParseError {
         /// How many arguments, total, were expected.
         expected: usize,
 
+        /// Byte range of the first extra argument in the source.
+        span: Range<usize>,
+
         /// Location of the error.
         backtrace: Backtrace,
     },
@@ -57,13 +71,46 @@ pub enum ParseError {
     #[snafu(display("incorrect argument type"))]
     #[non_exhaustive]
     ArgumentType {
+        /// Byte range of the argument in the source.
+        span: Range<usize>,
+
         /// The location of the error.
         backtrace: Backtrace,
     },
 }
 
+impl ParseError {
+    /// Byte range into the source code where the error occurred.
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            Self::ImmediateTooLarge { span, .. }
+            | Self::Lexer { span, .. }
+            | Self::MissingArgument { span, .. }
+            | Self::ExtraArgument { span, .. }
+            | Self::ArgumentType { span, .. } => span.clone(),
+        }
+    }
+
+    /// A short code identifying the kind of error, which doesn't change
+    /// between releases.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::ImmediateTooLarge { .. } => "E100",
+            Self::Lexer { .. } => "E101",
+            Self::MissingArgument { .. } => "E102",
+            Self::ExtraArgument { .. } => "E103",
+            Self::ArgumentType { .. } => "E104",
+        }
+    }
+}
+
 impl From<Error<Rule>> for ParseError {
     fn from(err: Error<Rule>) -> Self {
-        Lexer {}.into_error(Box::new(err))
+        let span = match err.location {
+            InputLocation::Pos(pos) => pos..pos,
+            InputLocation::Span((start, end)) => start..end,
+        };
+
+        Lexer { span }.into_error(Box::new(err))
     }
 }
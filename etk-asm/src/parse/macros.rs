@@ -1,4 +1,4 @@
-use super::args::Signature;
+use super::args::{span, Signature};
 use super::error::ParseError;
 use super::expression;
 use super::parser::Rule;
@@ -23,6 +23,7 @@ pub(crate) fn parse(pair: Pair<Rule>) -> Result<AbstractOp, ParseError> {
 }
 
 pub(crate) fn parse_builtin(pair: Pair<Rule>) -> Result<Node, ParseError> {
+    let invocation = span(&pair);
     let mut pairs = pair.into_inner();
     let pair = pairs.next().unwrap();
     assert!(pairs.next().is_none());
@@ -30,15 +31,15 @@ pub(crate) fn parse_builtin(pair: Pair<Rule>) -> Result<Node, ParseError> {
 
     let node = match rule {
         Rule::import => {
-            let args = <(PathBuf,)>::parse_arguments(pair.into_inner())?;
+            let args = <(PathBuf,)>::parse_arguments(pair, invocation)?;
             Node::Import(args.0)
         }
         Rule::include => {
-            let args = <(PathBuf,)>::parse_arguments(pair.into_inner())?;
+            let args = <(PathBuf,)>::parse_arguments(pair, invocation)?;
             Node::Include(args.0)
         }
         Rule::include_hex => {
-            let args = <(PathBuf,)>::parse_arguments(pair.into_inner())?;
+            let args = <(PathBuf,)>::parse_arguments(pair, invocation)?;
             Node::IncludeHex(args.0)
         }
         Rule::push_macro => {
@@ -14,6 +14,7 @@ mod parser {
 }
 
 use std::convert::TryInto;
+use std::ops::Range;
 
 use self::{
     error::ParseError,
@@ -26,17 +27,19 @@ use etk_ops::cancun::Op;
 use num_bigint::BigInt;
 use pest::{iterators::Pair, Parser};
 
-pub(crate) fn parse_asm(asm: &str) -> Result<Vec<Node>, ParseError> {
-    let mut program: Vec<Node> = Vec::new();
+/// Parse `asm` into nodes, each with its byte range in `asm`.
+pub(crate) fn parse_asm(asm: &str) -> Result<Vec<(Node, Range<usize>)>, ParseError> {
+    let mut program = Vec::new();
 
     let pairs = AsmParser::parse(Rule::program, asm)?;
     for pair in pairs {
+        let span = args::span(&pair);
         let node = match pair.as_rule() {
             Rule::builtin => macros::parse_builtin(pair)?,
             Rule::EOI => continue,
             _ => parse_abstract_op(pair)?.into(),
         };
-        program.push(node);
+        program.push((node, span));
     }
 
     Ok(program)
@@ -67,12 +70,13 @@ fn parse_push(pair: Pair<Rule>) -> Result<AbstractOp, ParseError> {
     let operand = pair.next().unwrap();
 
     let spec = Op::<()>::push(size).unwrap();
+    let span = args::span(&operand);
     let expr = expression::parse(operand)?;
 
     if let Ok(val) = expr.eval() {
         let max = BigInt::pow(&BigInt::from(2u32), (8 * size).try_into().unwrap());
         if val >= max {
-            return error::ImmediateTooLarge.fail();
+            return error::ImmediateTooLarge { span }.fail();
         }
     }
 
@@ -92,6 +96,11 @@ mod tests {
     use num_bigint::Sign;
     use std::path::PathBuf;
 
+    fn parse_asm(asm: &str) -> Result<Vec<Node>, ParseError> {
+        let nodes = super::parse_asm(asm)?;
+        Ok(nodes.into_iter().map(|(node, _)| node).collect())
+    }
+
     macro_rules! nodes {
         ($($x:expr),+ $(,)?) => (
             vec![$(Node::from($x)),+]
@@ -392,10 +401,7 @@ mod tests {
         );
         assert!(matches!(
             parse_asm(&asm),
-            Err(ParseError::ExtraArgument {
-                expected: 1,
-                backtrace: _
-            })
+            Err(ParseError::ExtraArgument { expected: 1, .. })
         ))
     }
 
@@ -411,7 +417,7 @@ mod tests {
             Err(ParseError::MissingArgument {
                 got: 0,
                 expected: 1,
-                backtrace: _,
+                ..
             })
         ))
     }
@@ -597,4 +603,42 @@ mod tests {
         ];
         assert_eq!(parse_asm(&asm).unwrap(), expected);
     }
+
+    #[test]
+    fn span_immediate_too_large() {
+        let asm = "push1 1\npush1 256\n";
+        let err = parse_asm(asm).unwrap_err();
+
+        assert_matches!(err, ParseError::ImmediateTooLarge { .. });
+        assert_eq!(err.code(), "E100");
+        assert_eq!(&asm[err.span()], "256");
+    }
+
+    #[test]
+    fn span_lexer() {
+        let asm = "push1 1\nfoo bar\n";
+        let err = parse_asm(asm).unwrap_err();
+
+        assert_matches!(err, ParseError::Lexer { .. });
+        assert_eq!(err.code(), "E101");
+        assert_eq!(err.span(), 8..8);
+    }
+
+    #[test]
+    fn span_argument() {
+        let asm = r#"%import("foo.asm", "bar.asm")"#;
+        let err = parse_asm(asm).unwrap_err();
+        assert_eq!(err.code(), "E103");
+        assert_eq!(&asm[err.span()], r#""bar.asm""#);
+
+        let asm = "%import(0x44)";
+        let err = parse_asm(asm).unwrap_err();
+        assert_eq!(err.code(), "E104");
+        assert_eq!(&asm[err.span()], "0x44");
+
+        let asm = "%import()";
+        let err = parse_asm(asm).unwrap_err();
+        assert_eq!(err.code(), "E102");
+        assert_eq!(&asm[err.span()], "%import()");
+    }
 }
@@ -0,0 +1,283 @@
+//! Structural checks on bytecode that don't require executing it.
+//!
+//! See [`validate`] for more details.
+use crate::disasm::immediate_to_usize;
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// The opcode specifier (`Op<()>`) of a fork from [`etk_ops`].
+pub trait Specifier: From<u8> {
+    /// Length of the immediate argument following the opcode.
+    fn extra_len(&self) -> usize;
+
+    /// Returns true if this is `jump` or `jumpi`.
+    fn is_jump(&self) -> bool;
+
+    /// Returns true if this is a `jumpdest`.
+    fn is_jump_target(&self) -> bool;
+
+    /// Returns true if the opcode is assigned an instruction in the fork.
+    fn is_defined(&self) -> bool;
+}
+
+macro_rules! impl_specifier {
+    ($fork:ident) => {
+        impl Specifier for etk_ops::$fork::Op<()> {
+            fn extra_len(&self) -> usize {
+                etk_ops::$fork::Operation::extra_len(self)
+            }
+
+            fn is_jump(&self) -> bool {
+                etk_ops::$fork::Operation::is_jump(self)
+            }
+
+            fn is_jump_target(&self) -> bool {
+                etk_ops::$fork::Operation::is_jump_target(self)
+            }
+
+            fn is_defined(&self) -> bool {
+                etk_ops::$fork::Operation::is_defined(self)
+            }
+        }
+    };
+}
+
+impl_specifier!(london);
+impl_specifier!(shanghai);
+impl_specifier!(cancun);
+
+/// A structural problem found in bytecode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Issue {
+    /// A push immediately followed by a `jump` or `jumpi` has a destination
+    /// that isn't a `jumpdest`.
+    InvalidJumpDest {
+        /// Location of the push.
+        offset: usize,
+
+        /// The immediate of the push, or `None` if it doesn't fit in a `usize`.
+        destination: Option<usize>,
+    },
+
+    /// A push instruction's immediate extends past the end of the code.
+    TruncatedPush {
+        /// Location of the push.
+        offset: usize,
+    },
+
+    /// An opcode with no instruction in the selected fork.
+    UndefinedOpcode {
+        /// Location of the opcode.
+        offset: usize,
+
+        /// The undefined opcode.
+        opcode: u8,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidJumpDest {
+                offset,
+                destination: Some(destination),
+            } => write!(
+                f,
+                "jump at 0x{:x} targets 0x{:x}, which is not a jumpdest",
+                offset, destination
+            ),
+            Self::InvalidJumpDest {
+                offset,
+                destination: None,
+            } => write!(f, "jump at 0x{:x} targets an out of range offset", offset),
+            Self::TruncatedPush { offset } => {
+                write!(f, "push at 0x{:x} runs past the end of the code", offset)
+            }
+            Self::UndefinedOpcode { offset, opcode } => {
+                write!(f, "undefined opcode 0x{:02x} at 0x{:x}", opcode, offset)
+            }
+        }
+    }
+}
+
+/// Check `code` for jumps to non-`jumpdest` offsets, truncated pushes, and
+/// opcodes that are undefined in the fork of `S`.
+///
+/// `S` is the opcode specifier of the fork to check against, for example
+/// `etk_ops::london::Op<()>`. Only jumps whose destination is pushed by the
+/// immediately preceding instruction are checked.
+///
+/// ## Example
+///
+/// ```rust
+/// use etk_asm::validate::{validate, Issue};
+/// use etk_ops::cancun::Op;
+/// use hex_literal::hex;
+///
+/// // push1 0x04, jump, stop, jumpdest
+/// assert!(validate::<Op<()>>(&hex!("600456005b")).is_empty());
+///
+/// // push1 0x03, jump, stop
+/// let issues = validate::<Op<()>>(&hex!("60035600"));
+/// assert_eq!(
+///     issues,
+///     [Issue::InvalidJumpDest { offset: 0, destination: Some(3) }],
+/// );
+/// ```
+pub fn validate<S>(code: &[u8]) -> Vec<Issue>
+where
+    S: Specifier,
+{
+    let mut issues = Vec::new();
+    let mut jumpdests = BTreeSet::new();
+
+    // Pushes that feed a jump, as the offset of the push and its immediate.
+    let mut jumps = Vec::new();
+    let mut previous_push: Option<(usize, &[u8])> = None;
+
+    let mut offset = 0;
+    while offset < code.len() {
+        let opcode = code[offset];
+        let spec = S::from(opcode);
+
+        if !spec.is_defined() {
+            issues.push(Issue::UndefinedOpcode { offset, opcode });
+        }
+
+        if spec.is_jump_target() {
+            jumpdests.insert(offset);
+        }
+
+        if spec.is_jump() {
+            if let Some(push) = previous_push {
+                jumps.push(push);
+            }
+        }
+
+        let start = offset + 1;
+        let end = start + spec.extra_len();
+
+        if end > code.len() {
+            issues.push(Issue::TruncatedPush { offset });
+            break;
+        }
+
+        previous_push = match spec.extra_len() {
+            0 => None,
+            _ => Some((offset, &code[start..end])),
+        };
+
+        offset = end;
+    }
+
+    for (offset, imm) in jumps {
+        let destination = immediate_to_usize(imm);
+
+        match destination {
+            Some(d) if jumpdests.contains(&d) => continue,
+            _ => issues.push(Issue::InvalidJumpDest {
+                offset,
+                destination,
+            }),
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use etk_ops::{cancun, london};
+
+    use hex_literal::hex;
+
+    use super::*;
+
+    #[test]
+    fn valid() {
+        // push1 0x01, push1 0x0b, jumpi, invalid, jumpdest, push2 0x0006, jump,
+        // jumpdest
+        let code = hex!("6001 600b 57 fe 5b 610006 56 5b");
+        assert_eq!(validate::<cancun::Op<()>>(&code), []);
+    }
+
+    #[test]
+    fn push_to_non_jumpdest() {
+        // push1 0x05, jump, stop, stop, stop, jumpdest
+        let code = hex!("6005 56 00 00 00 5b");
+        assert_eq!(
+            validate::<cancun::Op<()>>(&code),
+            [Issue::InvalidJumpDest {
+                offset: 0,
+                destination: Some(5),
+            }]
+        );
+    }
+
+    #[test]
+    fn push_to_jumpdest_byte_in_immediate() {
+        // push1 0x05, jump, push32 with 0x5b at offset 0x05, jumpdest
+        let code = hex!(
+            "6005 56"
+            "7f 005b000000000000000000000000000000000000000000000000000000000000"
+            "5b"
+        );
+        assert_eq!(
+            validate::<cancun::Op<()>>(&code),
+            [Issue::InvalidJumpDest {
+                offset: 0,
+                destination: Some(5),
+            }]
+        );
+    }
+
+    #[test]
+    fn push_to_out_of_range() {
+        let code = hex!("7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff 56");
+        assert_eq!(
+            validate::<cancun::Op<()>>(&code),
+            [Issue::InvalidJumpDest {
+                offset: 0,
+                destination: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn truncated_push() {
+        // jumpdest, push4 with only two bytes
+        let code = hex!("5b 63 0102");
+        assert_eq!(
+            validate::<cancun::Op<()>>(&code),
+            [Issue::TruncatedPush { offset: 1 }]
+        );
+    }
+
+    #[test]
+    fn undefined_opcode() {
+        let code = hex!("5f 0c");
+        assert_eq!(
+            validate::<cancun::Op<()>>(&code),
+            [Issue::UndefinedOpcode {
+                offset: 1,
+                opcode: 0x0c,
+            }]
+        );
+    }
+
+    #[test]
+    fn undefined_opcode_in_fork() {
+        // push0 only exists from Shanghai onwards.
+        let code = hex!("5f");
+        assert_eq!(validate::<cancun::Op<()>>(&code), []);
+        assert_eq!(
+            validate::<london::Op<()>>(&code),
+            [Issue::UndefinedOpcode {
+                offset: 0,
+                opcode: 0x5f,
+            }]
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use etk_asm::ingest::{Error, Ingest};
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+fn labels(cwd: PathBuf, input: &str) -> Result<BTreeMap<String, usize>, Error> {
+    std::env::set_current_dir(cwd).unwrap();
+
+    let mut output = Vec::new();
+    let mut ingest = Ingest::with_artifact(&mut output);
+    ingest.ingest_file(input)?;
+
+    Ok(ingest.artifact().unwrap().labels.clone())
+}
+
+// Changing the working directory affects the whole process, so this is the
+// only test in this file.
+#[test]
+fn labels_independent_of_working_directory() -> Result<(), Error> {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("asm")
+        .join("artifact");
+
+    let outside = labels(root.join("..").join(".."), "asm/artifact/main.etk")?;
+    let inside = labels(root.clone(), "main.etk")?;
+    let below = labels(root.join("lib"), "../main.etk")?;
+
+    let expected: BTreeMap<_, _> = vec![("lib/lib.etk:start".to_owned(), 1)]
+        .into_iter()
+        .collect();
+
+    assert_eq!(outside, expected);
+    assert_eq!(inside, expected);
+    assert_eq!(below, expected);
+
+    Ok(())
+}
@@ -2,20 +2,28 @@
 mod opts;
 #[path = "disease/selectors.rs"]
 mod selectors;
+#[path = "disease/symbols.rs"]
+mod symbols;
 
 use crate::opts::Opts;
 use crate::selectors::DisplayOp;
+use crate::symbols::{DisplayTarget, Symbols};
 
-use etk_asm::disasm::{Disassembler, Offset};
+use etk_asm::disasm::{immediate_to_usize, Disassembler, Offset};
 
 use etk_cli::errors::WithSources;
 
-use etk_dasm::blocks::basic::Separator;
+use etk_dasm::blocks::basic::{self, BasicBlock, Separator};
+use etk_dasm::eof::{self, Container};
+
+use etk_ops::cancun::{Op, Operation};
 
 use snafu::{Backtrace, Snafu};
 
+use std::collections::HashSet;
+use std::fmt;
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, Read, Write};
 
 #[derive(Debug, Snafu)]
 enum Error {
@@ -24,6 +32,18 @@ enum Error {
         source: std::io::Error,
         backtrace: Backtrace,
     },
+
+    #[snafu(context(false))]
+    Eof {
+        source: eof::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(context(false))]
+    Symbols {
+        source: symbols::Error,
+        backtrace: Backtrace,
+    },
 }
 
 fn main() {
@@ -42,29 +62,181 @@ fn run() -> Result<(), Error> {
     let opts: Opts = clap::Parser::parse();
 
     let mut input = opts.src.open()?;
-    let mut disasm = Disassembler::new();
-
-    std::io::copy(&mut input, &mut disasm)?;
+    let mut code = Vec::new();
+    input.read_to_end(&mut code)?;
 
     let mut out: Box<dyn Write> = match opts.out_file {
         Some(path) => Box::new(File::create(path)?),
         None => Box::new(std::io::stdout()),
     };
 
+    let symbols = match opts.artifact {
+        Some(path) => Some(Symbols::open_artifact(path)?),
+        None => opts.symbols.map(Symbols::open).transpose()?,
+    };
+
+    if !eof::is_eof(&code) {
+        let mut disasm = Disassembler::new();
+        disasm.write_all(&code)?;
+
+        write_blocks(&mut out, &separate(disasm.ops()), symbols.as_ref())?;
+        return Ok(());
+    }
+
+    let container = Container::parse(&code)?;
+    write_container(&mut out, &container)?;
+
+    Ok(())
+}
+
+/// Disassemble each code section of `container` on its own, starting from
+/// offset zero, and print the remaining sections as hex.
+///
+/// Symbols name legacy `jumpdest` offsets, so they aren't used here.
+fn write_container<W>(out: &mut W, container: &Container) -> Result<(), Error>
+where
+    W: Write,
+{
+    for (index, section) in container.code.iter().enumerate() {
+        writeln!(out, "# code section {}", index)?;
+        write_blocks(out, &separate(eof::disassemble(section)?), None)?;
+    }
+
+    for (index, nested) in container.containers.iter().enumerate() {
+        writeln!(
+            out,
+            "# container section {}: 0x{}",
+            index,
+            hex::encode(nested)
+        )?;
+    }
+
+    writeln!(out, "# data section: 0x{}", hex::encode(container.data))?;
+
+    Ok(())
+}
+
+fn separate<I, T>(ops: T) -> Vec<BasicBlock<I>>
+where
+    I: basic::Instruction,
+    T: IntoIterator<Item = Offset<I>>,
+{
     let mut separator = Separator::new();
 
-    separator.push_all(disasm.ops());
+    separator.push_all(ops);
+
+    separator
+        .take()
+        .into_iter()
+        .chain(separator.finish())
+        .collect()
+}
+
+/// An instruction that [`write_blocks`] knows how to print.
+trait Listing: basic::Instruction {
+    /// The offset of the instruction following this one, at `offset`.
+    fn next(&self, offset: usize) -> usize;
 
-    let basic_blocks = separator.take().into_iter().chain(separator.finish());
+    /// The jump destination this instruction provides to `next`, if any.
+    fn target(&self, next: &Self) -> Option<usize>;
+
+    /// Print this instruction, annotated with the name of its jump
+    /// destination, if known.
+    fn fmt_line(&self, f: &mut fmt::Formatter, target: Option<&str>) -> fmt::Result;
+}
+
+impl Listing for Op<[u8]> {
+    fn next(&self, offset: usize) -> usize {
+        Offset::new(offset, *self).next()
+    }
+
+    fn target(&self, next: &Self) -> Option<usize> {
+        // A push immediately followed by a jump is the jump's destination.
+        if next.is_jump() {
+            self.immediate().and_then(immediate_to_usize)
+        } else {
+            None
+        }
+    }
+
+    fn fmt_line(&self, f: &mut fmt::Formatter, target: Option<&str>) -> fmt::Result {
+        match target {
+            Some(name) => fmt::Display::fmt(&DisplayTarget { op: self, name }, f),
+            None => fmt::Display::fmt(&DisplayOp(*self), f),
+        }
+    }
+}
+
+impl Listing for eof::Instruction {
+    fn next(&self, offset: usize) -> usize {
+        offset + basic::Instruction::size(self)
+    }
+
+    fn target(&self, _: &Self) -> Option<usize> {
+        // EOF jumps carry their destination in their own immediate.
+        None
+    }
+
+    fn fmt_line(&self, f: &mut fmt::Formatter, _: Option<&str>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+struct Line<'a, I> {
+    op: &'a I,
+    target: Option<&'a str>,
+}
+
+impl<'a, I> fmt::Display for Line<'a, I>
+where
+    I: Listing,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.op.fmt_line(f, self.target)
+    }
+}
+
+fn write_blocks<W, I>(
+    out: &mut W,
+    blocks: &[BasicBlock<I>],
+    symbols: Option<&Symbols>,
+) -> io::Result<()>
+where
+    W: Write,
+    I: Listing,
+{
+    let destinations: HashSet<usize> = blocks
+        .iter()
+        .filter(|b| b.ops.first().map(I::is_jump_target).unwrap_or(false))
+        .map(|b| b.offset)
+        .collect();
+
+    for block in blocks {
+        if let Some(symbols) = symbols {
+            if destinations.contains(&block.offset) {
+                writeln!(out, "{}:", symbols.name(block.offset))?;
+            }
+        }
 
-    for block in basic_blocks {
         let mut offset = block.offset;
-        for op in block.ops {
-            let len = op.size();
-            let off = Offset::new(offset, DisplayOp(op));
-            offset += len;
+        let mut ops = block.ops.iter().peekable();
+
+        while let Some(op) = ops.next() {
+            let target = match (symbols, ops.peek()) {
+                (Some(symbols), Some(next)) => op
+                    .target(next)
+                    .filter(|dest| destinations.contains(dest))
+                    .map(|dest| symbols.name(dest)),
+                _ => None,
+            };
+
+            let line = Line {
+                op,
+                target: target.as_deref(),
+            };
+            writeln!(out, "{}", Offset::new(offset, line))?;
 
-            writeln!(out, "{}", off)?;
+            offset = op.next(offset);
         }
 
         writeln!(out)?;
@@ -72,3 +244,100 @@ fn run() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use etk_ops::cancun::*;
+
+    use hex_literal::hex;
+
+    use super::*;
+
+    fn blocks() -> Vec<BasicBlock> {
+        separate(vec![
+            Offset::new(0x00, Op::from(Push1([0x04]))),
+            Offset::new(0x02, Op::from(Jump)),
+            Offset::new(0x03, Op::from(Invalid)),
+            Offset::new(0x04, Op::from(JumpDest)),
+            Offset::new(0x05, Op::from(Push1([0x08]))),
+            Offset::new(0x07, Op::from(Jump)),
+            Offset::new(0x08, Op::from(JumpDest)),
+            Offset::new(0x09, Op::from(Stop)),
+        ])
+    }
+
+    #[test]
+    fn write_blocks_without_symbols() {
+        let mut out = Vec::new();
+        write_blocks(&mut out, &blocks(), None).unwrap();
+        let txt = String::from_utf8(out).unwrap();
+
+        assert!(!txt.contains("label_0x"));
+        assert!(!txt.contains("start:"));
+    }
+
+    #[test]
+    fn write_blocks_with_symbols() {
+        let symbols = Symbols::from_reader(r#"{ "0x04": "start" }"#.as_bytes()).unwrap();
+
+        let mut out = Vec::new();
+        write_blocks(&mut out, &blocks(), Some(&symbols)).unwrap();
+        let txt = String::from_utf8(out).unwrap();
+
+        let expected = concat!(
+            "   0:   push1 0x04 # start\n",
+            "   2:   jump\n",
+            "\n",
+            "   3:   invalid\n",
+            "\n",
+            "start:\n",
+            "   4:   jumpdest\n",
+            "   5:   push1 0x08 # label_0x8\n",
+            "   7:   jump\n",
+            "\n",
+            "label_0x8:\n",
+            "   8:   jumpdest\n",
+            "   9:   stop\n",
+            "\n",
+        );
+
+        assert_eq!(txt, expected);
+    }
+
+    #[test]
+    fn write_eof_container() {
+        let bytes = hex!(
+            "ef0001"
+            "010008"
+            "020002" "0008" "0001"
+            "ff0002"
+            "00"
+            "00800001" "00800000"
+            "5f" "e10001" "00" "e50001"
+            "00"
+            "abcd"
+        );
+        let container = Container::parse(&bytes).unwrap();
+
+        let mut out = Vec::new();
+        write_container(&mut out, &container).unwrap();
+        let txt = String::from_utf8(out).unwrap();
+
+        let expected = concat!(
+            "# code section 0\n",
+            "   0:   push0\n",
+            "   1:   rjumpi 0x0001\n",
+            "\n",
+            "   4:   stop\n",
+            "\n",
+            "   5:   jumpf 0x0001\n",
+            "\n",
+            "# code section 1\n",
+            "   0:   stop\n",
+            "\n",
+            "# data section: 0xabcd\n",
+        );
+
+        assert_eq!(txt, expected);
+    }
+}
@@ -15,4 +15,19 @@ pub struct Opts {
         help = "path to output file (defaults to stdout)"
     )]
     pub out_file: Option<PathBuf>,
+
+    #[structopt(
+        short = 's',
+        long = "symbols",
+        help = "path to a JSON object mapping code offsets to label names (ignored for EOF containers)"
+    )]
+    pub symbols: Option<PathBuf>,
+
+    #[structopt(
+        short = 'a',
+        long = "artifact",
+        conflicts_with = "symbols",
+        help = "path to an artifact written by `eas --artifact`, whose labels name code offsets (ignored for EOF containers)"
+    )]
+    pub artifact: Option<PathBuf>,
 }
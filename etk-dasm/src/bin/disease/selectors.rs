@@ -1,7 +1,10 @@
 use etk_4byte::reverse_selector;
 
+use etk_asm::disasm::immediate_to_usize;
+
 use etk_ops::cancun::{Op, Operation};
 
+use std::convert::TryFrom;
 use std::fmt;
 
 #[derive(Debug)]
@@ -15,20 +18,8 @@ impl DisplayOp {
     }
 
     fn selector(&self) -> Option<u32> {
-        let mut imm = self.0.immediate()?;
-
-        // Strip leading zeros.
-        while !imm.is_empty() && imm[0] == 0 {
-            imm = &imm[1..];
-        }
-
-        let mut be_bytes = [0u8; 4];
-
-        let start = be_bytes.len().checked_sub(imm.len())?;
-        let end = be_bytes.len();
-        be_bytes[start..end].copy_from_slice(imm);
-
-        Some(u32::from_be_bytes(be_bytes))
+        let selector = immediate_to_usize(self.0.immediate()?)?;
+        u32::try_from(selector).ok()
     }
 }
 
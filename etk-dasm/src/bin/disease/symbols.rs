@@ -0,0 +1,182 @@
+use etk_asm::artifact::{self, Artifact};
+
+use etk_ops::cancun::{Op, Operation};
+
+use snafu::{Backtrace, ResultExt, Snafu};
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+#[derive(Debug, Snafu)]
+#[snafu(context(suffix(false)))]
+pub enum Error {
+    #[snafu(display("unable to read symbol file"))]
+    Open {
+        source: io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("symbol file is not a JSON object of offsets to names"))]
+    Json {
+        source: serde_json::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("unable to read artifact"))]
+    ReadArtifact {
+        source: artifact::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("symbol file contains an invalid offset `{}`", key))]
+    InvalidOffset {
+        key: String,
+        source: std::num::ParseIntError,
+        backtrace: Backtrace,
+    },
+}
+
+/// Names for code offsets, typically taken from a compiler's output.
+///
+/// Offsets without a name receive a synthesized `label_0x...` name.
+#[derive(Debug, Default)]
+pub struct Symbols {
+    names: HashMap<usize, String>,
+}
+
+impl Symbols {
+    pub fn open<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(path).context(Open)?;
+        Self::from_reader(file)
+    }
+
+    /// Read a JSON object mapping offsets (decimal, or hexadecimal with a `0x`
+    /// prefix) to names.
+    pub fn from_reader<R>(reader: R) -> Result<Self, Error>
+    where
+        R: Read,
+    {
+        let raw: HashMap<String, String> = serde_json::from_reader(reader).context(Json)?;
+        let mut names = HashMap::with_capacity(raw.len());
+
+        for (key, name) in raw {
+            let parsed = match key.strip_prefix("0x") {
+                Some(hex) => usize::from_str_radix(hex, 16),
+                None => key.parse(),
+            };
+
+            let offset = parsed.context(InvalidOffset { key })?;
+            names.insert(offset, name);
+        }
+
+        Ok(Self { names })
+    }
+
+    /// Read the labels of an artifact written by `eas --artifact`.
+    pub fn open_artifact<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(path).context(Open)?;
+        let artifact = Artifact::read_from(file).context(ReadArtifact)?;
+        Ok(Self::from_artifact(&artifact))
+    }
+
+    /// Name each offset after the label at that position in `artifact`. If
+    /// several labels share a position, the first in alphabetical order is
+    /// used.
+    pub fn from_artifact(artifact: &Artifact) -> Self {
+        let mut names = HashMap::with_capacity(artifact.labels.len());
+
+        for (name, position) in &artifact.labels {
+            names.entry(*position).or_insert_with(|| name.clone());
+        }
+
+        Self { names }
+    }
+
+    pub fn name(&self, offset: usize) -> Cow<'_, str> {
+        match self.names.get(&offset) {
+            Some(name) => Cow::Borrowed(name),
+            None => Cow::Owned(format!("label_0x{:x}", offset)),
+        }
+    }
+}
+
+/// Displays a push instruction whose immediate is a jump target, annotated
+/// with the target's name.
+#[derive(Debug)]
+pub struct DisplayTarget<'a> {
+    pub op: &'a Op<[u8]>,
+    pub name: &'a str,
+}
+
+impl<'a> fmt::Display for DisplayTarget<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.op.code())?;
+
+        if let Some(imm) = self.op.immediate() {
+            write!(f, " 0x{}", hex::encode(imm))?;
+        }
+
+        write!(f, " # {}", self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use etk_ops::cancun::*;
+
+    use super::*;
+
+    #[test]
+    fn from_reader_hex_and_decimal() {
+        let json = r#"{ "0x1a": "transfer", "4": "fallback" }"#;
+        let symbols = Symbols::from_reader(json.as_bytes()).unwrap();
+
+        assert_eq!(symbols.name(0x1a), "transfer");
+        assert_eq!(symbols.name(4), "fallback");
+        assert_eq!(symbols.name(5), "label_0x5");
+    }
+
+    #[test]
+    fn from_reader_invalid_offset() {
+        let json = r#"{ "0xzz": "oops" }"#;
+        let err = Symbols::from_reader(json.as_bytes()).unwrap_err();
+
+        assert!(matches!(err, Error::InvalidOffset { ref key, .. } if key == "0xzz"));
+    }
+
+    #[test]
+    fn from_artifact_labels() {
+        let mut artifact = Artifact::default();
+        artifact.labels.insert("start".to_owned(), 0);
+        artifact.labels.insert("lib.etk:loop".to_owned(), 4);
+        artifact.labels.insert("lib.etk:again".to_owned(), 4);
+
+        let symbols = Symbols::from_artifact(&artifact);
+
+        assert_eq!(symbols.name(0), "start");
+        assert_eq!(symbols.name(4), "lib.etk:again");
+        assert_eq!(symbols.name(5), "label_0x5");
+    }
+
+    #[test]
+    fn format_target() {
+        let op = Push1([0x1a]).into();
+        let txt = DisplayTarget {
+            op: &op,
+            name: "transfer",
+        }
+        .to_string();
+
+        assert_eq!(txt, "push1 0x1a # transfer");
+    }
+}
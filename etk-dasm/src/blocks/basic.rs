@@ -3,31 +3,84 @@ use etk_asm::disasm::Offset;
 
 use etk_ops::cancun::{Op, Operation};
 
+/// An instruction that can be grouped into a [`BasicBlock`].
+///
+/// Implemented for legacy instructions ([`Op<[u8]>`]) and for the
+/// instructions of EOF code sections ([`crate::eof::Instruction`]).
+pub trait Instruction {
+    /// Length of the instruction, including any immediate, in bytes.
+    fn size(&self) -> usize;
+
+    /// Returns true if the instruction changes the program counter (other
+    /// than incrementing it.)
+    fn is_jump(&self) -> bool;
+
+    /// Returns true if the instruction is a valid destination for jumps.
+    fn is_jump_target(&self) -> bool;
+
+    /// Returns true if the instruction stops execution of the code.
+    fn is_exit(&self) -> bool;
+}
+
+impl Instruction for Op<[u8]> {
+    fn size(&self) -> usize {
+        Op::size(self)
+    }
+
+    fn is_jump(&self) -> bool {
+        <Self as Operation>::is_jump(self)
+    }
+
+    fn is_jump_target(&self) -> bool {
+        <Self as Operation>::is_jump_target(self)
+    }
+
+    fn is_exit(&self) -> bool {
+        <Self as Operation>::is_exit(self)
+    }
+}
+
 /// A list of EVM instructions with a single point of entry and a single exit.
 #[derive(Debug, Eq, PartialEq)]
-pub struct BasicBlock {
+pub struct BasicBlock<I = Op<[u8]>> {
     /// Position of the first instruction of this block in the entire program.
     pub offset: usize,
 
     /// List of instructions contained in the block.
-    pub ops: Vec<Op<[u8]>>,
+    pub ops: Vec<I>,
 }
 
-impl BasicBlock {
+impl<I> BasicBlock<I>
+where
+    I: Instruction,
+{
     /// Sum of the length of every instruction in this block.
     pub fn size(&self) -> usize {
-        self.ops.iter().map(Op::size).sum()
+        self.ops.iter().map(I::size).sum()
     }
 }
 
-/// Separate a sequence of [`Op<[u8]>`] into [`BasicBlock`].
-#[derive(Debug, Default)]
-pub struct Separator {
-    complete_blocks: Vec<BasicBlock>,
-    in_progress: Option<BasicBlock>,
+/// Separate a sequence of instructions, like [`Op<[u8]>`], into
+/// [`BasicBlock`].
+#[derive(Debug)]
+pub struct Separator<I = Op<[u8]>> {
+    complete_blocks: Vec<BasicBlock<I>>,
+    in_progress: Option<BasicBlock<I>>,
+}
+
+impl<I> Default for Separator<I> {
+    fn default() -> Self {
+        Self {
+            complete_blocks: Vec::new(),
+            in_progress: None,
+        }
+    }
 }
 
-impl Separator {
+impl<I> Separator<I>
+where
+    I: Instruction,
+{
     /// Create a default instance.
     pub fn new() -> Self {
         Self::default()
@@ -36,9 +89,9 @@ impl Separator {
     /// Read instructions from `iter` until it is empty.
     ///
     /// Returns `true` if any [`BasicBlock`] are ready.
-    pub fn push_all<I>(&mut self, iter: I) -> bool
+    pub fn push_all<T>(&mut self, iter: T) -> bool
     where
-        I: IntoIterator<Item = Offset<Op<[u8]>>>,
+        T: IntoIterator<Item = Offset<I>>,
     {
         let mut available = false;
         for item in iter.into_iter() {
@@ -49,7 +102,7 @@ impl Separator {
 
     /// Push a single instruction, returns `true` if a [`BasicBlock`] has been
     /// completed.
-    pub fn push(&mut self, off: Offset<Op<[u8]>>) -> bool {
+    pub fn push(&mut self, off: Offset<I>) -> bool {
         if off.item.is_jump_target() {
             // If we receive a jumpdest, start a new block beginning with it.
             let completed = self.in_progress.replace(BasicBlock {
@@ -89,14 +142,14 @@ impl Separator {
     }
 
     /// Remove all completed [`BasicBlock`].
-    pub fn take(&mut self) -> Vec<BasicBlock> {
+    pub fn take(&mut self) -> Vec<BasicBlock<I>> {
         std::mem::take(&mut self.complete_blocks)
     }
 
     /// Retrieve the last [`BasicBlock`] after all instructions have been
     /// consumed.
     #[must_use]
-    pub fn finish(&mut self) -> Option<BasicBlock> {
+    pub fn finish(&mut self) -> Option<BasicBlock<I>> {
         if self.complete_blocks.is_empty() {
             self.in_progress.take()
         } else {
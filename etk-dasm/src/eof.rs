@@ -0,0 +1,569 @@
+//! Parsing of EVM Object Format (EOF) containers, as described in
+//! [EIP-3540](https://eips.ethereum.org/EIPS/eip-3540).
+//!
+//! An EOF container separates code from data, and may hold several code
+//! sections. Use [`is_eof`] to detect a container, and [`Container::parse`] to
+//! split it into sections that can be disassembled independently.
+//!
+//! EOF code sections use instructions, like `rjump` and `callf`, that aren't
+//! part of [`etk_ops`], and some legacy instructions (like `jump`) are no
+//! longer allowed. Use [`disassemble`] instead of [`etk_asm::disasm`] to
+//! split a code section into [`Instruction`]s.
+use crate::blocks::basic;
+
+use etk_asm::disasm::Offset;
+
+use etk_ops::cancun::{Op, Operation};
+
+use std::convert::TryInto;
+use std::fmt;
+
+const MAGIC: [u8; 2] = [0xef, 0x00];
+const VERSION: u8 = 0x01;
+
+const KIND_TYPES: u8 = 0x01;
+const KIND_CODE: u8 = 0x02;
+const KIND_CONTAINER: u8 = 0x03;
+const KIND_DATA: u8 = 0xff;
+const TERMINATOR: u8 = 0x00;
+
+/// Errors that may arise while parsing an EOF container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The container ended before all of its header or sections were read.
+    Truncated,
+
+    /// The container's version is not supported.
+    UnsupportedVersion {
+        /// The version found in the header.
+        version: u8,
+    },
+
+    /// The header contained an unexpected byte.
+    InvalidHeader {
+        /// Position of the unexpected byte.
+        offset: usize,
+    },
+
+    /// The types section's size doesn't match the number of code sections.
+    InvalidTypes,
+
+    /// The container is longer than the sections described by its header.
+    TrailingBytes,
+
+    /// A code section ended partway through an instruction.
+    TruncatedInstruction {
+        /// Position of the instruction within its code section.
+        offset: usize,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "EOF container is truncated"),
+            Self::UnsupportedVersion { version } => {
+                write!(f, "unsupported EOF version {}", version)
+            }
+            Self::InvalidHeader { offset } => {
+                write!(f, "invalid EOF header at offset {}", offset)
+            }
+            Self::InvalidTypes => write!(f, "EOF types section doesn't match code sections"),
+            Self::TrailingBytes => write!(f, "EOF container has trailing bytes"),
+            Self::TruncatedInstruction { offset } => {
+                write!(f, "EOF instruction at offset {} is truncated", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Returns `true` if `bytes` begins with the EOF magic (`0xef00`), or `false`
+/// if it should be treated as legacy bytecode.
+pub fn is_eof(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+/// Metadata describing one code section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Type {
+    /// Number of stack elements the section consumes.
+    pub inputs: u8,
+
+    /// Number of stack elements the section returns, or `0x80` if it never
+    /// returns.
+    pub outputs: u8,
+
+    /// Maximum stack height reached while executing the section.
+    pub max_stack_height: u16,
+}
+
+/// An EOF container split into its sections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Container<'a> {
+    /// Version of the format.
+    pub version: u8,
+
+    /// One entry for each section in `code`.
+    pub types: Vec<Type>,
+
+    /// Code sections, each starting at its own offset zero.
+    pub code: Vec<&'a [u8]>,
+
+    /// Nested containers.
+    pub containers: Vec<&'a [u8]>,
+
+    /// The data section.
+    pub data: &'a [u8],
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self.offset.checked_add(len).ok_or(Error::Truncated)?;
+        let taken = self.bytes.get(self.offset..end).ok_or(Error::Truncated)?;
+        self.offset = end;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), Error> {
+        let offset = self.offset;
+        if self.u8()? == byte {
+            Ok(())
+        } else {
+            Err(Error::InvalidHeader { offset })
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.offset).copied()
+    }
+}
+
+impl<'a> Container<'a> {
+    /// Split `bytes` into its sections.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use etk_dasm::eof::Container;
+    /// use hex_literal::hex;
+    ///
+    /// let bytes = hex!("ef0001 010004 0200010001 ff0000 00 00800000 00");
+    /// let container = Container::parse(&bytes).unwrap();
+    ///
+    /// assert_eq!(container.code, [&[0x00][..]]);
+    /// assert!(container.data.is_empty());
+    /// ```
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, Error> {
+        let mut reader = Reader { bytes, offset: 0 };
+
+        for byte in MAGIC {
+            reader.expect(byte)?;
+        }
+
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion { version });
+        }
+
+        reader.expect(KIND_TYPES)?;
+        let types_size = reader.u16()? as usize;
+
+        reader.expect(KIND_CODE)?;
+        let code_count = reader.u16()? as usize;
+        if code_count == 0 {
+            return Err(Error::InvalidHeader {
+                offset: reader.offset - 2,
+            });
+        }
+
+        let mut code_sizes = Vec::with_capacity(code_count);
+        for _ in 0..code_count {
+            code_sizes.push(reader.u16()? as usize);
+        }
+
+        let mut container_sizes = Vec::new();
+        if reader.peek() == Some(KIND_CONTAINER) {
+            reader.expect(KIND_CONTAINER)?;
+            for _ in 0..reader.u16()? {
+                container_sizes.push(reader.u32()? as usize);
+            }
+        }
+
+        reader.expect(KIND_DATA)?;
+        let data_size = reader.u16()? as usize;
+
+        reader.expect(TERMINATOR)?;
+
+        if types_size != code_count * 4 {
+            return Err(Error::InvalidTypes);
+        }
+
+        let mut types = Vec::with_capacity(code_count);
+        for _ in 0..code_count {
+            types.push(Type {
+                inputs: reader.u8()?,
+                outputs: reader.u8()?,
+                max_stack_height: reader.u16()?,
+            });
+        }
+
+        let code = code_sizes
+            .into_iter()
+            .map(|size| reader.take(size))
+            .collect::<Result<_, _>>()?;
+
+        let containers = container_sizes
+            .into_iter()
+            .map(|size| reader.take(size))
+            .collect::<Result<_, _>>()?;
+
+        let data = reader.take(data_size)?;
+
+        if reader.offset != bytes.len() {
+            return Err(Error::TrailingBytes);
+        }
+
+        Ok(Self {
+            version,
+            types,
+            code,
+            containers,
+            data,
+        })
+    }
+}
+
+const RJUMP: u8 = 0xe0;
+const RJUMPI: u8 = 0xe1;
+const RJUMPV: u8 = 0xe2;
+const CALLF: u8 = 0xe3;
+const RETF: u8 = 0xe4;
+const JUMPF: u8 = 0xe5;
+const RETURNCONTRACT: u8 = 0xee;
+
+/// Instructions added (or renamed, like `jumpdest`) by EOF, with the length
+/// of their immediates. `rjumpv` is listed with only its first immediate byte,
+/// since the length of its jump table depends on that byte.
+const EOF_OPS: [(u8, &str, usize); 20] = [
+    (0x5b, "nop", 0),
+    (0xd0, "dataload", 0),
+    (0xd1, "dataloadn", 2),
+    (0xd2, "datasize", 0),
+    (0xd3, "datacopy", 0),
+    (RJUMP, "rjump", 2),
+    (RJUMPI, "rjumpi", 2),
+    (RJUMPV, "rjumpv", 1),
+    (CALLF, "callf", 2),
+    (RETF, "retf", 0),
+    (JUMPF, "jumpf", 2),
+    (0xe6, "dupn", 1),
+    (0xe7, "swapn", 1),
+    (0xe8, "exchange", 1),
+    (0xec, "eofcreate", 1),
+    (RETURNCONTRACT, "returncontract", 1),
+    (0xf7, "returndataload", 0),
+    (0xf8, "extcall", 0),
+    (0xf9, "extdelegatecall", 0),
+    (0xfb, "extstaticcall", 0),
+];
+
+/// Legacy instructions that aren't allowed in EOF code.
+const REMOVED_OPS: [u8; 16] = [
+    0x38, 0x39, 0x3b, 0x3c, 0x3f, 0x56, 0x57, 0x58, 0x5a, 0xf0, 0xf1, 0xf2, 0xf4, 0xf5, 0xfa, 0xff,
+];
+
+fn eof_op(code: u8) -> Option<(&'static str, usize)> {
+    EOF_OPS
+        .iter()
+        .find(|(c, _, _)| *c == code)
+        .map(|(_, mnemonic, len)| (*mnemonic, *len))
+}
+
+/// An instruction in an EOF code section: an opcode and its immediate bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// The opcode.
+    pub code: u8,
+
+    /// The bytes following the opcode that belong to this instruction. For
+    /// `rjumpv`, this includes the jump table.
+    pub immediate: Vec<u8>,
+}
+
+impl Instruction {
+    /// Human-readable name for this instruction, or `None` if its opcode
+    /// isn't allowed in EOF code.
+    pub fn mnemonic(&self) -> Option<String> {
+        if let Some((mnemonic, _)) = eof_op(self.code) {
+            Some(mnemonic.to_owned())
+        } else if REMOVED_OPS.contains(&self.code) {
+            None
+        } else {
+            let op = Op::<()>::from(self.code);
+            op.is_defined().then(|| op.to_string())
+        }
+    }
+}
+
+impl basic::Instruction for Instruction {
+    fn size(&self) -> usize {
+        1 + self.immediate.len()
+    }
+
+    fn is_jump(&self) -> bool {
+        matches!(self.code, RJUMP | RJUMPI | RJUMPV | CALLF | JUMPF)
+    }
+
+    fn is_jump_target(&self) -> bool {
+        false
+    }
+
+    fn is_exit(&self) -> bool {
+        match self.code {
+            RJUMP | RETF | JUMPF | RETURNCONTRACT => true,
+            _ if eof_op(self.code).is_some() => false,
+            _ => self.mnemonic().is_none() || Op::<()>::from(self.code).is_exit(),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.mnemonic() {
+            Some(mnemonic) => write!(f, "{}", mnemonic)?,
+            None => write!(f, "invalid_{:02x}", self.code)?,
+        }
+
+        if !self.immediate.is_empty() {
+            write!(f, " 0x{}", hex::encode(&self.immediate))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Split an EOF code section into instructions, using the immediate lengths
+/// of EOF (so, for example, `rjump` is followed by a two byte offset).
+///
+/// ## Example
+///
+/// ```rust
+/// use etk_dasm::eof::disassemble;
+///
+/// // push0, rjumpi +1, stop, stop
+/// let ops = disassemble(&[0x5f, 0xe1, 0x00, 0x01, 0x00, 0x00]).unwrap();
+///
+/// assert_eq!(ops[1].offset, 1);
+/// assert_eq!(ops[1].item.to_string(), "rjumpi 0x0001");
+/// assert_eq!(ops.len(), 4);
+/// ```
+pub fn disassemble(section: &[u8]) -> Result<Vec<Offset<Instruction>>, Error> {
+    let mut ops = Vec::new();
+    let mut offset = 0;
+
+    while offset < section.len() {
+        let code = section[offset];
+
+        let mut len = match eof_op(code) {
+            Some((_, len)) => len,
+            None if REMOVED_OPS.contains(&code) => 0,
+            None => Op::<()>::from(code).size() - 1,
+        };
+
+        if code == RJUMPV {
+            // The immediate is the highest index into the jump table, followed
+            // by the table of two byte offsets.
+            let max_index = section.get(offset + 1).copied().unwrap_or_default();
+            len += (usize::from(max_index) + 1) * 2;
+        }
+
+        let immediate = section
+            .get(offset + 1..offset + 1 + len)
+            .ok_or(Error::TruncatedInstruction { offset })?;
+
+        ops.push(Offset::new(
+            offset,
+            Instruction {
+                code,
+                immediate: immediate.to_vec(),
+            },
+        ));
+
+        offset += 1 + len;
+    }
+
+    Ok(ops)
+}
+
+#[cfg(test)]
+mod tests {
+    use hex_literal::hex;
+
+    use super::*;
+
+    #[test]
+    fn legacy_is_not_eof() {
+        assert!(!is_eof(&hex!("6000")));
+        assert!(!is_eof(&hex!("ef")));
+        assert!(is_eof(&hex!("ef0001")));
+    }
+
+    #[test]
+    fn parse_minimal() {
+        let bytes = hex!(
+            "ef0001"
+            "010008"
+            "020002" "0003" "0001"
+            "ff0002"
+            "00"
+            "00800001" "00800000"
+            "60015f" "00"
+            "abcd"
+        );
+
+        let container = Container::parse(&bytes).unwrap();
+
+        assert_eq!(container.version, 1);
+        assert_eq!(
+            container.types,
+            [
+                Type {
+                    inputs: 0,
+                    outputs: 0x80,
+                    max_stack_height: 1,
+                },
+                Type {
+                    inputs: 0,
+                    outputs: 0x80,
+                    max_stack_height: 0,
+                },
+            ]
+        );
+        assert_eq!(container.code, [&hex!("60015f")[..], &hex!("00")[..]]);
+        assert!(container.containers.is_empty());
+        assert_eq!(container.data, hex!("abcd"));
+
+        let ops = disassemble(container.code[0]).unwrap();
+        let txt: Vec<_> = ops.iter().map(ToString::to_string).collect();
+        assert_eq!(txt, ["   0:   push1 0x01", "   2:   push0"]);
+    }
+
+    #[test]
+    fn disassemble_immediates() {
+        let section = hex!(
+            "e10003"       // rjumpi +3
+            "e2010000fff0" // rjumpv [0, -16]
+            "e30001"       // callf 1
+            "d10020"       // dataloadn 32
+            "6001"         // push1 1
+            "5b"           // nop
+            "56"           // jump (removed)
+            "e50002"       // jumpf 2
+        );
+
+        let ops: Vec<_> = disassemble(&section)
+            .unwrap()
+            .into_iter()
+            .map(|op| (op.offset, op.item.to_string()))
+            .collect();
+
+        let expected = [
+            (0, "rjumpi 0x0003"),
+            (3, "rjumpv 0x010000fff0"),
+            (9, "callf 0x0001"),
+            (12, "dataloadn 0x0020"),
+            (15, "push1 0x01"),
+            (17, "nop"),
+            (18, "invalid_56"),
+            (19, "jumpf 0x0002"),
+        ];
+
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(offset, txt)| (*offset, txt.to_string()))
+            .collect();
+
+        assert_eq!(ops, expected);
+    }
+
+    #[test]
+    fn disassemble_truncated() {
+        let err = disassemble(&hex!("00 e20100ff")).unwrap_err();
+        assert_eq!(err, Error::TruncatedInstruction { offset: 1 });
+
+        let err = disassemble(&hex!("e0")).unwrap_err();
+        assert_eq!(err, Error::TruncatedInstruction { offset: 0 });
+    }
+
+    #[test]
+    fn parse_nested_container() {
+        let bytes = hex!(
+            "ef0001"
+            "010004"
+            "0200010001"
+            "03000100000002"
+            "ff0000"
+            "00"
+            "00800000"
+            "00"
+            "ef00"
+        );
+
+        let container = Container::parse(&bytes).unwrap();
+        assert_eq!(container.containers, [&hex!("ef00")[..]]);
+    }
+
+    #[test]
+    fn parse_unsupported_version() {
+        let err = Container::parse(&hex!("ef0002")).unwrap_err();
+        assert_eq!(err, Error::UnsupportedVersion { version: 2 });
+    }
+
+    #[test]
+    fn parse_truncated() {
+        let bytes = hex!("ef0001 010004 0200010004 ff0000 00 00800000 6001");
+        let err = Container::parse(&bytes).unwrap_err();
+        assert_eq!(err, Error::Truncated);
+    }
+
+    #[test]
+    fn parse_trailing() {
+        let bytes = hex!("ef0001 010004 0200010001 ff0000 00 00800000 00 00");
+        let err = Container::parse(&bytes).unwrap_err();
+        assert_eq!(err, Error::TrailingBytes);
+    }
+
+    #[test]
+    fn parse_mismatched_types() {
+        let bytes = hex!("ef0001 010008 0200010001 ff0000 00 00800000 00");
+        let err = Container::parse(&bytes).unwrap_err();
+        assert_eq!(err, Error::InvalidTypes);
+    }
+
+    #[test]
+    fn parse_missing_data_kind() {
+        let bytes = hex!("ef0001 010004 0200010001 040000 00 00800000 00");
+        let err = Container::parse(&bytes).unwrap_err();
+        assert_eq!(err, Error::InvalidHeader { offset: 11 });
+    }
+}
@@ -8,4 +8,5 @@
 #![deny(missing_debug_implementations)]
 
 pub mod blocks;
+pub mod eof;
 pub mod sym;
@@ -45,6 +45,9 @@ struct Op {
 
     #[serde(default)]
     jump_target: bool,
+
+    #[serde(skip)]
+    undefined: bool,
 }
 
 fn read_fork(name: &str) -> Result<[(String, Op); 256], Error> {
@@ -72,6 +75,7 @@ fn read_fork(name: &str) -> Result<[(String, Op); 256], Error> {
                 exits: true,
                 jump: false,
                 jump_target: false,
+                undefined: true,
             };
             (name, op)
         })
@@ -147,6 +151,11 @@ fn generate_fork(fork_name: &str) -> Result<(), Error> {
             /// the contract.
             fn is_exit(&self) -> bool;
 
+            /// Returns true if the current instruction is assigned to an opcode in
+            /// this fork, or false if it is one of the undefined `invalid_XX`
+            /// placeholders.
+            fn is_defined(&self) -> bool;
+
             /// How many stack elements this instruction pops.
             fn pops(&self) -> usize;
 
@@ -178,6 +187,7 @@ fn generate_fork(fork_name: &str) -> Result<(), Error> {
         let pops = op.pops;
         let pushes = op.pushes;
         let exit = op.exits;
+        let defined = !op.undefined;
 
         let generics;
         let variant_generics;
@@ -347,6 +357,7 @@ fn generate_fork(fork_name: &str) -> Result<(), Error> {
                 fn is_jump(&self) -> bool { #jump }
                 fn is_jump_target(&self) -> bool { #jump_target }
                 fn is_exit(&self) -> bool { #exit }
+                fn is_defined(&self) -> bool { #defined }
                 fn pops(&self) -> usize { #pops as usize }
                 fn pushes(&self) -> usize { #pushes as usize}
             }
@@ -512,6 +523,14 @@ fn generate_fork(fork_name: &str) -> Result<(), Error> {
                 }
             }
 
+            fn is_defined(&self) -> bool {
+                match self {
+                    #(
+                    Self::#names(n) => n.is_defined(),
+                    )*
+                }
+            }
+
             fn pops(&self) -> usize {
                 match self {
                     #(
@@ -753,6 +772,13 @@ fn generate_fork(fork_name: &str) -> Result<(), Error> {
                 }
             }
 
+            #[test]
+            fn defined() {
+                assert!(Op::<()>::from(Invalid).is_defined());
+                assert!(Op::<()>::from(Stop).is_defined());
+                assert!(!Op::<()>::from(0x0cu8).is_defined());
+            }
+
             #[test]
             fn code_through_str() {
                 for ii in 0..=u8::MAX {